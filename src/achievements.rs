@@ -0,0 +1,209 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::path::Path;
+
+const ACHIEVEMENTS_DATA: &str = "resources/achievements.ron";
+const UNLOCKED_FILE: &str = "achievements_unlocked.ron";
+
+// How many turns the HUD announces a newly unlocked achievement for.
+pub const NOTICE_TURNS: usize = 10;
+
+// Something that happened during the run, as achievements see it. How
+// names and levels match is described in resources/achievements.ron.
+#[derive(Clone, Debug, Deserialize)]
+pub enum EventPattern {
+    Killed(String),
+    PickedUp(String),
+    Used(String),
+    EnteredLevel(u32),
+    MapRevealed,
+    TileRevealed,
+    Died,
+    Won,
+}
+
+impl EventPattern {
+    pub fn matches(&self, event: &AchievementEvent) -> bool {
+        match (self, event) {
+            (EventPattern::Killed(name), AchievementEvent::Killed(victim)) => victim.contains(name),
+            (EventPattern::PickedUp(name), AchievementEvent::PickedUp(item))
+                | (EventPattern::Used(name), AchievementEvent::Used(item)) => item.contains(name),
+            (EventPattern::EnteredLevel(level), AchievementEvent::EnteredLevel(entered)) =>
+                *level == entered + 1,
+            (EventPattern::MapRevealed, AchievementEvent::MapRevealed)
+                | (EventPattern::TileRevealed, AchievementEvent::TileRevealed)
+                | (EventPattern::Died, AchievementEvent::Died)
+                | (EventPattern::Won, AchievementEvent::Won) => true,
+            _ => false,
+        }
+    }
+}
+
+// Which part of the run a count covers.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub enum Scope {
+    #[default]
+    Run,
+    ThisLevel,
+    Level(u32),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub enum Condition {
+    // How many times something has happened.
+    Count {
+        event: EventPattern,
+        #[serde(default)]
+        scope: Scope,
+        #[serde(default)]
+        at_least: usize,
+        #[serde(default = "unlimited")]
+        at_most: usize,
+    },
+    // The player is carrying a weapon with this name.
+    Wielding(String),
+    // Every tile of the current level the player could walk to has been
+    // seen.
+    LevelRevealed,
+}
+
+fn unlimited() -> usize {
+    usize::MAX
+}
+
+// One entry of resources/achievements.ron, which explains when it unlocks.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Achievement {
+    pub name: String,
+    pub description: String,
+    pub when: EventPattern,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+}
+
+// The events achievements care about, in a form that can be saved and
+// counted later.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AchievementEvent {
+    Killed(String),
+    PickedUp(String),
+    Used(String),
+    EnteredLevel(u32),
+    // Back up the stairs to a level already visited.
+    ClimbedToLevel(u32),
+    MapRevealed,
+    TileRevealed,
+    Died,
+    Won,
+}
+
+impl AchievementEvent {
+    pub fn from_game_event(event: &GameEvent) -> Option<Self> {
+        let event = match event {
+            GameEvent::EntityDied { victim, .. } if victim.is_player => AchievementEvent::Died,
+            GameEvent::EntityDied { victim, killer } if killer.is_player =>
+                AchievementEvent::Killed(victim.name.clone()),
+            GameEvent::ItemPickedUp { by, item } if by.is_player =>
+                AchievementEvent::PickedUp(item.name.clone()),
+            GameEvent::ItemUsed { by, item } if by.is_player =>
+                AchievementEvent::Used(item.name.clone()),
+            GameEvent::LevelEntered { level, going_down: true } =>
+                AchievementEvent::EnteredLevel(*level),
+            GameEvent::LevelEntered { level, going_down: false } =>
+                AchievementEvent::ClimbedToLevel(*level),
+            GameEvent::MapRevealed => AchievementEvent::MapRevealed,
+            GameEvent::TileRevealed { .. } => AchievementEvent::TileRevealed,
+            GameEvent::Victory => AchievementEvent::Won,
+            _ => return None,
+        };
+        Some(event)
+    }
+}
+
+// What has happened so far this run, and on which level, for the counts.
+// It is saved along with the game.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AchievementProgress {
+    pub level: u32,
+    history: Vec<(u32, AchievementEvent)>,
+}
+
+impl AchievementProgress {
+    pub fn new(start_level: u32) -> Self {
+        Self { level: start_level, history: Vec::new() }
+    }
+
+    // Every revealed tile would swamp the history, so those are only ever
+    // triggers.
+    pub fn record(&mut self, event: &AchievementEvent) {
+        if let AchievementEvent::EnteredLevel(level) | AchievementEvent::ClimbedToLevel(level) = event {
+            self.level = *level;
+        }
+        if *event != AchievementEvent::TileRevealed {
+            self.history.push((self.level, event.clone()));
+        }
+    }
+
+    pub fn count(&self, pattern: &EventPattern, scope: &Scope) -> usize {
+        self.history
+            .iter()
+            .filter(|(level, _)| match scope {
+                Scope::Run => true,
+                Scope::ThisLevel => *level == self.level,
+                Scope::Level(n) => level + 1 == *n,
+            })
+            .filter(|(_, event)| pattern.matches(event))
+            .count()
+    }
+}
+
+// Every achievement there is, and those unlocked in any run so far.
+#[derive(Clone, Debug, Default)]
+pub struct Achievements {
+    pub list: Vec<Achievement>,
+    pub unlocked: BTreeSet<String>,
+    // The latest unlock, and the turn it happened on, for the HUD.
+    pub notice: Option<(String, usize)>,
+    // Set when something was unlocked and the file needs writing.
+    pub changed: bool,
+}
+
+impl Achievements {
+    pub fn load() -> Self {
+        let list = File::open(ACHIEVEMENTS_DATA)
+            .map_err(|e| e.to_string())
+            .and_then(|file| ron::de::from_reader(file).map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| {
+                println!("Warning: unable to load {ACHIEVEMENTS_DATA}: {e}");
+                Vec::new()
+            });
+        let unlocked = if Path::new(UNLOCKED_FILE).exists() {
+            File::open(UNLOCKED_FILE)
+                .map_err(|e| e.to_string())
+                .and_then(|file| ron::de::from_reader(file).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| {
+                    println!("Warning: unable to load {UNLOCKED_FILE}: {e}");
+                    BTreeSet::new()
+                })
+        } else {
+            BTreeSet::new()
+        };
+        Self { list, unlocked, notice: None, changed: false }
+    }
+
+    pub fn unlock(&mut self, name: &str, turn: usize) {
+        self.unlocked.insert(name.to_string());
+        self.notice = Some((name.to_string(), turn));
+        self.changed = true;
+    }
+
+    pub fn save(&self) {
+        let contents = ron::ser::to_string_pretty(&self.unlocked, ron::ser::PrettyConfig::new())
+            .expect("Unable to serialize achievements");
+        if let Err(e) = atomic_write(UNLOCKED_FILE, &contents) {
+            println!("Warning: unable to write {UNLOCKED_FILE}: {e}");
+        }
+    }
+}
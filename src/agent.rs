@@ -0,0 +1,276 @@
+use crate::prelude::*;
+use crate::headless::Action;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// What kind of thing the player can see.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SeenKind {
+    Enemy,
+    Item,
+    Amulet,
+}
+
+// The reference agent doesn't read every field, other agents may.
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub struct SeenEntity {
+    pub pos: Point,
+    pub name: String,
+    pub kind: SeenKind,
+    pub health: Option<Health>,
+}
+
+// An item the player carries. Its place in the inventory is the number that
+// uses it, counting from one.
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub struct InventoryItem {
+    pub name: String,
+    pub healing: Option<i32>,
+    pub reveals_map: bool,
+}
+
+// Everything the player knows about the game, and nothing more: the tiles
+// they have seen, what is in sight right now, and what they carry.
+#[allow(dead_code)]
+pub struct AgentView {
+    // Only the tiles the player has seen are kept, never the whole map.
+    known_tiles: HashMap<Point, TileType>,
+    map_size: Point,
+    steps: StepRule,
+    pub player_pos: Point,
+    pub health: Health,
+    pub map_level: u32,
+    pub visible_tiles: HashSet<Point>,
+    pub entities: Vec<SeenEntity>,
+    pub inventory: Vec<InventoryItem>,
+}
+
+impl AgentView {
+    pub fn of(ecs: &World, resources: &Resources) -> Self {
+        let (player, player_pos, health, player_info, fov) =
+            <(Entity, &Point, &Health, &Player, &FieldOfView)>::query()
+                .iter(ecs)
+                .map(|(entity, pos, health, player, fov)| (*entity, *pos, *health, *player, fov))
+                .next()
+                .unwrap();
+
+        let entities = <(Entity, &Point, &Name)>::query()
+            .iter(ecs)
+            .filter(|(_, pos, _)| fov.visible_tiles.contains(pos))
+            .filter_map(|(entity, pos, name)| {
+                let entry = ecs.entry_ref(*entity).ok()?;
+                let kind = if entry.get_component::<Enemy>().is_ok() {
+                    SeenKind::Enemy
+                } else if entry.get_component::<AmuletOfYala>().is_ok() {
+                    SeenKind::Amulet
+                } else if entry.get_component::<Item>().is_ok() {
+                    SeenKind::Item
+                } else {
+                    return None;
+                };
+                Some(SeenEntity {
+                    pos: *pos,
+                    name: name.0.clone(),
+                    kind,
+                    health: entry.get_component::<Health>().ok().copied(),
+                })
+            })
+            .collect();
+
+        // Listed in the same order as player_input numbers them.
+        let inventory = <(Entity, &Item, &Carried)>::query()
+            .iter(ecs)
+            .filter(|(_, _, carried)| carried.0 == player)
+            .filter_map(|(entity, _, _)| {
+                let entry = ecs.entry_ref(*entity).ok()?;
+                Some(InventoryItem {
+                    name: entry.get_component::<Name>().map_or_else(|_| String::new(), |n| n.0.clone()),
+                    healing: entry.get_component::<ProvidesHealing>().ok().map(|h| h.amount),
+                    reveals_map: entry.get_component::<ProvidesDungeonMap>().is_ok(),
+                })
+            })
+            .collect();
+
+        let map = resources.get::<Map>().unwrap();
+        let known_tiles = (0..map.tiles.len())
+            .filter(|idx| map.revealed_tiles[*idx])
+            .map(|idx| map.index_to_point2d(idx))
+            .chain(fov.visible_tiles.iter().copied().filter(|pt| map.in_bounds(*pt)))
+            .map(|pt| (pt, map.tiles[map.point2d_to_index(pt)]))
+            .collect();
+
+        Self {
+            known_tiles,
+            map_size: Point::new(map.width, map.height),
+            steps: map.steps,
+            player_pos,
+            health,
+            map_level: player_info.map_level,
+            visible_tiles: fov.visible_tiles.clone(),
+            entities,
+            inventory,
+        }
+    }
+
+    // The tile at the given point, if the player has seen it.
+    pub fn tile_at(&self, pt: Point) -> Option<TileType> {
+        self.known_tiles.get(&pt).copied()
+    }
+
+    // Every tile the player has seen.
+    pub fn known_points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.known_tiles.keys().copied()
+    }
+
+    pub fn in_bounds(&self, pt: Point) -> bool {
+        pt.x >= 0 && pt.x < self.map_size.x && pt.y >= 0 && pt.y < self.map_size.y
+    }
+
+    // Whether the player could take a step between the two points, as far
+    // as they know. Tiles they haven't seen are taken to be walls.
+    pub fn is_step(&self, from: Point, to: Point) -> bool {
+        let can_enter = |pt: Point| matches!(self.tile_at(pt),
+            Some(TileType::Floor | TileType::Exit | TileType::UpStairs));
+        let delta = to - from;
+        delta != Point::zero() && delta.x.abs() <= 1 && delta.y.abs() <= 1
+            && can_enter(to) && self.steps.allows(from, delta, can_enter)
+    }
+}
+
+// Something that can play the game in place of a human. Every turn it is
+// shown what the player knows and picks what they do.
+pub trait Agent {
+    fn act(&mut self, view: &AgentView) -> Action;
+}
+
+// The agents that can be picked with --agent.
+pub fn agent_by_name(name: &str) -> Option<Box<dyn Agent>> {
+    match name {
+        "explorer" => Some(Box::new(Explorer::default())),
+        _ => None,
+    }
+}
+
+// A reference agent, good enough to play whole runs end to end: it drinks
+// a potion when badly hurt, fights whatever comes close, picks up what it
+// finds, explores until nothing is left unseen, then takes the exit.
+#[derive(Default)]
+pub struct Explorer {
+    turns: usize,
+    level: Option<u32>,
+    // Where items have been seen on this level, so the explorer still goes
+    // for them once they are out of sight.
+    items: HashSet<Point>,
+}
+
+impl Agent for Explorer {
+    fn act(&mut self, view: &AgentView) -> Action {
+        self.turns += 1;
+        let carried = |wanted: fn(&InventoryItem) -> bool| view.inventory
+            .iter()
+            .position(wanted)
+            .map(|n| Action::UseItem(n + 1));
+
+        if view.health.current * 2 <= view.health.max {
+            if let Some(action) = carried(|item| item.healing.is_some()) {
+                return action;
+            }
+        }
+        if let Some(action) = carried(|item| item.reveals_map) {
+            return action;
+        }
+
+        if self.level != Some(view.map_level) {
+            self.level = Some(view.map_level);
+            self.items.clear();
+        }
+        self.items.retain(|pos| !view.visible_tiles.contains(pos));
+        self.items.extend(view.entities
+            .iter()
+            .filter(|e| matches!(e.kind, SeenKind::Item | SeenKind::Amulet))
+            .map(|e| e.pos));
+
+        let weakest_adjacent = view.entities
+            .iter()
+            .filter(|e| e.kind == SeenKind::Enemy)
+            .filter(|e| view.is_step(view.player_pos, e.pos))
+            .min_by_key(|e| e.health.map_or(0, |h| h.current));
+        if let Some(enemy) = weakest_adjacent {
+            return Action::Attack(enemy.pos - view.player_pos);
+        }
+        if self.items.contains(&view.player_pos) {
+            return Action::PickUp;
+        }
+
+        // Monsters come to the explorer, so it only goes looking for items
+        // and for what it hasn't seen yet.
+        for goal in [&self.items, &frontier(view)] {
+            if let Some(step) = first_step(view, goal, false) {
+                return Action::Move(step);
+            }
+        }
+
+        // Nothing left to explore, so head for the way down.
+        let exit: HashSet<Point> = view.known_points()
+            .filter(|pt| view.tile_at(*pt) == Some(TileType::Exit))
+            .collect();
+        if let Some(step) = first_step(view, &exit, false) {
+            return Action::Move(step);
+        }
+
+        // Stairs can block a corridor. Going up and coming back may land the
+        // explorer on the far side of them.
+        if let Some(step) = first_step(view, &frontier(view), true) {
+            return Action::Move(step);
+        }
+
+        // Stuck: try each direction in turn.
+        Action::Move(STEPS[self.turns % STEPS.len()])
+    }
+}
+
+// Known floor next to a tile that hasn't been seen yet.
+fn frontier(view: &AgentView) -> HashSet<Point> {
+    view.known_points()
+        .filter(|pt| view.tile_at(*pt) == Some(TileType::Floor))
+        .filter(|pt| STEPS.iter().any(|d| {
+            let next = *pt + *d;
+            view.in_bounds(next) && view.tile_at(next).is_none()
+        }))
+        .collect()
+}
+
+// The first step along the shortest known path to any of the goals. Stairs
+// lead off the level, so they are stepped around unless they are the goal
+// themselves or the path is allowed through them.
+fn first_step(view: &AgentView, goals: &HashSet<Point>, through_stairs: bool) -> Option<Point> {
+    if goals.is_empty() || goals.contains(&view.player_pos) {
+        return None;
+    }
+    let mut came_from = HashMap::new();
+    let mut open = VecDeque::from([view.player_pos]);
+    came_from.insert(view.player_pos, view.player_pos);
+    while let Some(pos) = open.pop_front() {
+        if goals.contains(&pos) {
+            let mut step = pos;
+            while came_from[&step] != view.player_pos {
+                step = came_from[&step];
+            }
+            return Some(step - view.player_pos);
+        }
+        for delta in STEPS {
+            let next = pos + delta;
+            let passable = view.is_step(pos, next) && match view.tile_at(next) {
+                Some(TileType::Floor) => true,
+                Some(TileType::Exit | TileType::UpStairs) => through_stairs || goals.contains(&next),
+                _ => false,
+            };
+            if passable && !came_from.contains_key(&next) {
+                came_from.insert(next, pos);
+                open.push_back(next);
+            }
+        }
+    }
+    None
+}
@@ -1,28 +1,199 @@
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
 
-// The camera acts as your game's window into the world. 
+// How closely the camera looks at the dungeon. Near draws it with the 32x32
+// font, Far at half that size, so four times as much of the map fits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Zoom {
+    #[default]
+    Near,
+    Far,
+}
+
+impl Zoom {
+    // How many map tiles are shown across each 32x32 tile of the window.
+    pub fn scale(self) -> i32 {
+        match self {
+            Zoom::Near => 1,
+            Zoom::Far => 2,
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            Zoom::Near => Zoom::Far,
+            Zoom::Far => Zoom::Near,
+        }
+    }
+}
+
+// The camera acts as your game's window into the world.
 // It defines the section of the map that is currently visible.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Camera {
     pub left_x: i32,
     pub right_x: i32,
     pub top_y: i32,
     pub bottom_y: i32,
+    pub zoom: Zoom,
+    // Leave the camera still while the player walks about the middle of
+    // the view, rather than keeping them centred.
+    pub dead_zone: bool,
 }
 
 impl Camera {
-    pub fn new(player_position: Point) -> Self {
-        Self {
-            left_x: player_position.x - DISPLAY_WIDTH/2,
-            right_x: player_position.x + DISPLAY_WIDTH/2,
-            top_y: player_position.y - DISPLAY_HEIGHT/2,
-            bottom_y: player_position.y + DISPLAY_HEIGHT/2,
+    pub fn new(player_position: Point, map: &Map, zoom: Zoom, dead_zone: bool) -> Self {
+        let mut camera = Self {
+            left_x: 0,
+            right_x: 0,
+            top_y: 0,
+            bottom_y: 0,
+            zoom,
+            dead_zone,
+        };
+        camera.centre_on(player_position, map);
+        camera
+    }
+
+    // The size of the view, in map tiles.
+    pub fn width(&self) -> i32 {
+        DISPLAY_WIDTH * self.zoom.scale()
+    }
+
+    pub fn height(&self) -> i32 {
+        DISPLAY_HEIGHT * self.zoom.scale()
+    }
+
+    pub fn centre_on(&mut self, position: Point, map: &Map) {
+        self.place(position.x - self.width()/2, position.y - self.height()/2, map);
+    }
+
+    pub fn on_player_move(&mut self, player_position: Point, map: &Map) {
+        if !self.dead_zone {
+            self.centre_on(player_position, map);
+            return;
         }
+
+        // Scroll only as far as it takes to keep the player within the
+        // middle half of the view.
+        let (margin_x, margin_y) = (self.width()/4, self.height()/4);
+        let left_x = self.left_x.clamp(
+            player_position.x - self.width() + 1 + margin_x,
+            player_position.x - margin_x
+        );
+        let top_y = self.top_y.clamp(
+            player_position.y - self.height() + 1 + margin_y,
+            player_position.y - margin_y
+        );
+        self.place(left_x, top_y, map);
+    }
+
+    // Keep the view on the map: it stops at the edges, and a map smaller
+    // than the view sits in the middle of it.
+    fn place(&mut self, left_x: i32, top_y: i32, map: &Map) {
+        let (width, height) = (self.width(), self.height());
+        self.left_x = if map.width <= width {
+            (map.width - width) / 2
+        } else {
+            left_x.clamp(0, map.width - width)
+        };
+        self.top_y = if map.height <= height {
+            (map.height - height) / 2
+        } else {
+            top_y.clamp(0, map.height - height)
+        };
+        self.right_x = self.left_x + width;
+        self.bottom_y = self.top_y + height;
+    }
+
+    // The map position drawn at the top left of the view.
+    pub fn offset(&self) -> Point {
+        Point::new(self.left_x, self.top_y)
+    }
+
+    pub fn contains(&self, pt: Point) -> bool {
+        pt.x >= self.left_x && pt.x < self.right_x
+            && pt.y >= self.top_y && pt.y < self.bottom_y
     }
 
-    pub fn on_player_move(&mut self, player_position: Point) {
-        self.left_x = player_position.x - DISPLAY_WIDTH/2; 
-        self.right_x = player_position.x + DISPLAY_WIDTH/2; 
-        self.top_y = player_position.y - DISPLAY_HEIGHT/2; 
-        self.bottom_y = player_position.y + DISPLAY_HEIGHT/2;
+    // The nearest position to the given one that is both in view and on the
+    // map, for cursors that slide along the edge of the view.
+    pub fn clamp(&self, pt: Point, map: &Map) -> Point {
+        Point::new(
+            pt.x.clamp(self.left_x.max(0), self.right_x.min(map.width) - 1),
+            pt.y.clamp(self.top_y.max(0), self.bottom_y.min(map.height) - 1),
+        )
     }
-}
\ No newline at end of file
+
+    // How many cells of the 8x8 text console cover one map tile, across
+    // and down.
+    pub fn text_cells(&self) -> i32 {
+        4 / self.zoom.scale()
+    }
+
+    // The text console cell at the top left of a map tile.
+    pub fn text_pos(&self, map_pos: Point) -> Point {
+        (map_pos - self.offset()) * self.text_cells()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(camera: &Camera) -> (i32, i32, i32, i32) {
+        (camera.left_x, camera.top_y, camera.right_x, camera.bottom_y)
+    }
+
+    #[test]
+    fn the_view_stops_at_the_edges_of_the_map() {
+        let map = Map::new(120, 75);
+        let camera = |pos| Camera::new(pos, &map, Zoom::Near, false);
+        assert_eq!(view(&camera(Point::new(60, 37))), (40, 25, 80, 50));
+        assert_eq!(view(&camera(Point::new(0, 0))), (0, 0, 40, 25));
+        assert_eq!(view(&camera(Point::new(119, 74))), (80, 50, 120, 75));
+
+        let far = Camera::new(Point::new(0, 0), &map, Zoom::Far, false);
+        assert_eq!(view(&far), (0, 0, 80, 50));
+    }
+
+    #[test]
+    fn a_map_smaller_than_the_view_sits_in_the_middle() {
+        let map = Map::new(30, 20);
+        for pos in [Point::new(0, 0), Point::new(29, 19), Point::new(15, 10)] {
+            let camera = Camera::new(pos, &map, Zoom::Near, false);
+            assert_eq!(view(&camera), (-5, -2, 35, 23));
+        }
+        let camera = Camera::new(Point::new(0, 0), &map, Zoom::Far, false);
+        assert_eq!(view(&camera), (-25, -15, 55, 35));
+
+        // Cursors still stay on the map, even where the view hangs over it.
+        let camera = Camera::new(Point::new(0, 0), &map, Zoom::Near, false);
+        assert_eq!(camera.clamp(Point::new(-3, 25), &map), Point::new(0, 19));
+    }
+
+    #[test]
+    fn the_dead_zone_only_scrolls_near_the_edge_of_the_view() {
+        let map = Map::new(120, 75);
+        let mut camera = Camera::new(Point::new(60, 37), &map, Zoom::Near, true);
+        assert_eq!(view(&camera), (40, 25, 80, 50));
+
+        camera.on_player_move(Point::new(65, 40), &map);
+        assert_eq!(view(&camera), (40, 25, 80, 50));
+
+        // Ten tiles from the right of the view is as far as the player gets.
+        camera.on_player_move(Point::new(70, 37), &map);
+        assert_eq!(view(&camera), (41, 25, 81, 50));
+        camera.on_player_move(Point::new(60, 37), &map);
+        assert_eq!(view(&camera), (41, 25, 81, 50));
+
+        // It still stops at the edges of the map.
+        camera.on_player_move(Point::new(119, 74), &map);
+        assert_eq!(view(&camera), (80, 50, 120, 75));
+
+        let mut centred = Camera::new(Point::new(60, 37), &map, Zoom::Near, false);
+        centred.on_player_move(Point::new(65, 40), &map);
+        assert_eq!(view(&centred), (45, 28, 85, 53));
+    }
+}
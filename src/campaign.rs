@@ -0,0 +1,110 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use ron::de::from_reader;
+use std::fs::File;
+
+const CAMPAIGN_FILE: &str = "resources/campaign.ron";
+
+// One level of the campaign, as described in resources/campaign.ron.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct CampaignLevel {
+    // Left out, the architect and theme are picked at random.
+    pub architect: Option<ArchitectKind>,
+    pub theme: Option<ThemeKind>,
+    // The size of the level's map, which can be smaller or larger than the
+    // screen.
+    #[serde(default = "default_width")]
+    pub width: i32,
+    #[serde(default = "default_height")]
+    pub height: i32,
+    // The most entities spawned from templates on the level.
+    pub monster_budget: usize,
+    // Templates listing any of these levels can be spawned.
+    pub template_levels: Vec<usize>,
+    // What waits at the far end of the level.
+    pub exit: bool,
+    pub amulet: bool,
+    pub boss: Option<String>,
+}
+
+fn default_width() -> i32 {
+    MAP_WIDTH
+}
+
+fn default_height() -> i32 {
+    MAP_HEIGHT
+}
+
+// Smaller maps leave no room for the rooms and the fortress prefab.
+const MIN_MAP_SIZE: i32 = 20;
+
+// The levels of a run, read once when it starts and kept as a resource, so
+// editing the file never changes a run already under way.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Campaign {
+    pub levels: Vec<CampaignLevel>,
+}
+
+impl Campaign {
+    pub fn load() -> Result<Self, String> {
+        let file = File::open(CAMPAIGN_FILE).map_err(|e| format!("{CAMPAIGN_FILE}: {e}"))?;
+        let campaign: Self = from_reader(file).map_err(|e| format!("{CAMPAIGN_FILE}: {e}"))?;
+        campaign.check().map_err(|e| format!("{CAMPAIGN_FILE}: {e}"))?;
+        Ok(campaign)
+    }
+
+    // A campaign has to be one the player can win: every level but the last
+    // leads on down, and the last holds the amulet.
+    fn check(&self) -> Result<(), String> {
+        let Some((last, others)) = self.levels.split_last() else {
+            return Err("the campaign has no levels".to_string());
+        };
+        if self.levels.iter().any(|l| l.width < MIN_MAP_SIZE || l.height < MIN_MAP_SIZE) {
+            return Err(format!("levels must be at least {MIN_MAP_SIZE} tiles wide and high"));
+        }
+        if let Some(n) = others.iter().position(|l| !l.exit) {
+            return Err(format!("level {} has no exit", n + 1));
+        }
+        if !last.amulet {
+            return Err("the last level doesn't hold the amulet".to_string());
+        }
+        Ok(())
+    }
+
+    // Levels past the end of the campaign repeat the last one.
+    pub fn level(&self, map_level: u32) -> &CampaignLevel {
+        let idx = (map_level as usize).min(self.levels.len() - 1);
+        &self.levels[idx]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(levels: &str) -> Campaign {
+        ron::de::from_str(&format!("Campaign(levels: [{levels}])")).unwrap()
+    }
+
+    const ON: &str = "CampaignLevel(monster_budget: 1, template_levels: [0], exit: true, amulet: false),";
+    const LAST: &str = "CampaignLevel(monster_budget: 1, template_levels: [0], exit: false, amulet: true),";
+
+    #[test]
+    fn the_shipped_campaign_can_be_won() {
+        assert!(Campaign::load().is_ok());
+    }
+
+    #[test]
+    fn every_level_but_the_last_needs_an_exit() {
+        assert!(campaign(&format!("{ON}{ON}{LAST}")).check().is_ok());
+        assert!(campaign(&format!("{ON}{LAST}{LAST}")).check().is_err_and(|e| e.contains("level 2")));
+        assert!(campaign(&format!("{ON}{ON}")).check().is_err_and(|e| e.contains("amulet")));
+        assert!(campaign("").check().is_err());
+    }
+
+    #[test]
+    fn levels_need_room_for_their_rooms() {
+        let small = "CampaignLevel(width: 10, monster_budget: 1, template_levels: [0], exit: false, amulet: true),";
+        assert!(campaign(small).check().is_err_and(|e| e.contains("at least")));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::prelude::*;
+
+const USAGE: &str = "\
+Usage: rusty-crawler [OPTIONS]
+
+Options:
+  --seed N                         Play the dungeon generated from seed N
+  --architect rooms|drunkard|automata
+                                   Build every level with this architect
+  --theme dungeon|forest           Draw every level with this theme
+  --start-level N                  Start on dungeon level N
+  --templates PATH                 Spawn entities from this template file
+  --no-corner-cutting              Forbid diagonal steps past the corner of a wall
+  --replay PATH                    Play back a recorded replay
+  --headless                       Run without a window
+  --script PATH                    Actions for a headless run (default: stdin)
+  --agent NAME                     Let an agent play a headless run (explorer)
+  --simulate N                     Let an agent play N runs, from --seed on, and report
+  --report PATH                    Write the simulation report as CSV, or JSON for .json
+  --max-turns N                    Stop an agent's run after N turns (default: 10000)
+  --wizard                         Start with wizard mode switched on
+  --windowed WxH                   Open a window of W by H pixels
+  --help                           Show this message";
+
+// Everything that can be set when launching the game.
+#[derive(Clone, Debug, Default)]
+pub struct Cli {
+    pub seed: Option<Seed>,
+    pub settings: GameSettings,
+    pub replay: Option<String>,
+    pub headless: bool,
+    pub script: Option<String>,
+    pub agent: Option<String>,
+    pub max_turns: Option<usize>,
+    pub simulate: Option<usize>,
+    pub report: Option<String>,
+    pub window: Option<(u32, u32)>,
+    pub wizard: bool,
+}
+
+impl Cli {
+    // Parse the command line, printing the usage and exiting if it can't
+    // be understood.
+    pub fn parse() -> Self {
+        match Self::parse_from(std::env::args().skip(1)) {
+            Ok(Some(cli)) => cli,
+            Ok(None) => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("Error: {e}\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // Returns None if help was asked for.
+    fn parse_from<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Self>, String> {
+        let mut cli = Self::default();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or(format!("{flag} needs a value"));
+            match flag.as_str() {
+                "--seed" => cli.seed = Some(Seed(parse_number(&flag, &value()?)?)),
+                "--architect" => cli.settings.architect = Some(match value()?.as_str() {
+                    "rooms" => ArchitectKind::Rooms,
+                    "drunkard" => ArchitectKind::Drunkard,
+                    "automata" => ArchitectKind::Automata,
+                    other => return Err(format!("unknown architect [{other}]")),
+                }),
+                "--theme" => cli.settings.theme = Some(match value()?.as_str() {
+                    "dungeon" => ThemeKind::Dungeon,
+                    "forest" => ThemeKind::Forest,
+                    other => return Err(format!("unknown theme [{other}]")),
+                }),
+                "--start-level" => {
+                    let level: u32 = parse_number(&flag, &value()?)?;
+                    cli.settings.start_level = level.checked_sub(1)
+                        .ok_or("dungeon levels start at 1")?;
+                }
+                "--templates" => cli.settings.templates = Some(value()?),
+                "--no-corner-cutting" => cli.settings.no_corner_cutting = true,
+                "--replay" => cli.replay = Some(value()?),
+                "--headless" => cli.headless = true,
+                "--script" => cli.script = Some(value()?),
+                "--agent" => {
+                    let name = value()?;
+                    if agent_by_name(&name).is_none() {
+                        return Err(format!("unknown agent [{name}]"));
+                    }
+                    cli.agent = Some(name);
+                }
+                "--max-turns" => cli.max_turns = Some(parse_number(&flag, &value()?)?),
+                "--simulate" => cli.simulate = Some(parse_number(&flag, &value()?)?),
+                "--report" => cli.report = Some(value()?),
+                "--windowed" => {
+                    let size = value()?;
+                    let (width, height) = size.split_once('x')
+                        .ok_or(format!("window size [{size}] should look like 1280x800"))?;
+                    cli.window = Some((parse_number(&flag, width)?, parse_number(&flag, height)?));
+                    if cli.window.is_some_and(|(width, height)| width == 0 || height == 0) {
+                        return Err(format!("window size [{size}] can't be empty"));
+                    }
+                }
+                "--wizard" => cli.wizard = true,
+                "--help" | "-h" => return Ok(None),
+                other => return Err(format!("unknown option [{other}]")),
+            }
+        }
+        cli.check()?;
+        Ok(Some(cli))
+    }
+
+    // Refuse options that would be silently ignored in the mode asked for.
+    fn check(&self) -> Result<(), String> {
+        let windowless = self.headless || self.simulate.is_some();
+        let conflicts = [
+            (self.agent.is_some() && !windowless,
+                "--agent only works with --headless or --simulate"),
+            (self.report.is_some() && self.simulate.is_none(),
+                "--report only works with --simulate"),
+            (self.max_turns.is_some() && self.agent.is_none() && self.simulate.is_none(),
+                "--max-turns only works with --agent or --simulate"),
+            (self.script.is_some() && !self.headless,
+                "--script only works with --headless"),
+            (self.script.is_some() && (self.agent.is_some() || self.replay.is_some() || self.simulate.is_some()),
+                "--script can't be combined with --agent, --replay or --simulate"),
+            (self.replay.is_some() && (self.agent.is_some() || self.simulate.is_some()),
+                "--replay can't be combined with --agent or --simulate"),
+            (self.replay.is_some() && (self.seed.is_some() || self.settings != GameSettings::default()),
+                "--replay plays back the seed and settings it was recorded with"),
+            (self.window.is_some() && windowless,
+                "--windowed can't be combined with --headless or --simulate"),
+            (self.wizard && windowless,
+                "--wizard can't be combined with --headless or --simulate"),
+        ];
+        match conflicts.iter().find(|(conflict, _)| *conflict) {
+            Some((_, e)) => Err((*e).to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{flag} expects a number, not [{value}]"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Option<Cli>, String> {
+        Cli::parse_from(args.split_whitespace().map(String::from))
+    }
+
+    fn cli(args: &str) -> Cli {
+        parse(args).unwrap().unwrap()
+    }
+
+    #[test]
+    fn every_option_is_understood() {
+        assert_eq!(cli("--seed 42").seed, Some(Seed(42)));
+        assert_eq!(cli("--architect rooms").settings.architect, Some(ArchitectKind::Rooms));
+        assert_eq!(cli("--architect drunkard").settings.architect, Some(ArchitectKind::Drunkard));
+        assert_eq!(cli("--architect automata").settings.architect, Some(ArchitectKind::Automata));
+        assert_eq!(cli("--theme dungeon").settings.theme, Some(ThemeKind::Dungeon));
+        assert_eq!(cli("--theme forest").settings.theme, Some(ThemeKind::Forest));
+        assert_eq!(cli("--start-level 3").settings.start_level, 2);
+        assert_eq!(cli("--templates monsters.ron").settings.templates.as_deref(), Some("monsters.ron"));
+        assert!(cli("--no-corner-cutting").settings.no_corner_cutting);
+        assert_eq!(cli("--replay run.ron").replay.as_deref(), Some("run.ron"));
+        assert!(cli("--headless").headless);
+        assert_eq!(cli("--headless --script moves.txt").script.as_deref(), Some("moves.txt"));
+        assert_eq!(cli("--headless --agent explorer").agent.as_deref(), Some("explorer"));
+        assert_eq!(cli("--headless --agent explorer --max-turns 50").max_turns, Some(50));
+        assert_eq!(cli("--simulate 10").simulate, Some(10));
+        assert_eq!(cli("--simulate 10 --report runs.csv").report.as_deref(), Some("runs.csv"));
+        assert!(cli("--wizard").wizard);
+        assert_eq!(cli("--windowed 1280x800").window, Some((1280, 800)));
+        assert!(parse("--help").unwrap().is_none());
+        assert!(parse("-h").unwrap().is_none());
+    }
+
+    #[test]
+    fn bad_values_are_errors() {
+        for args in [
+            "--seed", "--seed lots", "--start-level 0", "--start-level -1",
+            "--architect maze", "--theme desert", "--headless --agent nobody",
+            "--simulate many", "--windowed 1280", "--windowed 1280x", "--windowed x800",
+            "--windowed widexhigh", "--windowed 0x800", "--frobnicate",
+        ] {
+            assert!(parse(args).is_err(), "{args}");
+        }
+    }
+
+    #[test]
+    fn conflicting_options_are_errors() {
+        for args in [
+            "--agent explorer",
+            "--report runs.csv",
+            "--max-turns 50",
+            "--headless --max-turns 50",
+            "--script moves.txt",
+            "--headless --agent explorer --script moves.txt",
+            "--headless --replay run.ron --script moves.txt",
+            "--simulate 10 --script moves.txt",
+            "--headless --replay run.ron --agent explorer",
+            "--simulate 10 --replay run.ron",
+            "--replay run.ron --seed 42",
+            "--replay run.ron --theme forest",
+            "--headless --windowed 1280x800",
+            "--simulate 10 --windowed 1280x800",
+            "--headless --wizard",
+            "--simulate 10 --wizard",
+        ] {
+            assert!(parse(args).is_err(), "{args}");
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub use crate::prelude::*;
 use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Render {
@@ -7,15 +8,15 @@ pub struct Render {
     pub glyph: FontCharType,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Player {
     pub map_level: u32,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Enemy;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MovingRandomly;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -24,28 +25,32 @@ pub struct WantsToMove {
     pub destination: Point,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Health {
     pub current: i32,
     pub max: i32,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Name(pub String);
 
+// What the player learns by looking at something.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Description(pub String);
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct WantsToAttack {
     pub attacker: Entity,
     pub victim: Entity,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ChasingPlayer;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Item;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AmuletOfYala;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -65,12 +70,12 @@ impl FieldOfView {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ProvidesHealing {
     pub amount: i32,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ProvidesDungeonMap;
 
 #[derive(Clone, PartialEq)]
@@ -80,10 +85,110 @@ pub struct Carried(pub Entity);
 pub struct ActivateItem {
     pub used_by: Entity,
     pub item: Entity,
+    // Where a ranged item was aimed. Anything else is used on its user.
+    pub target: Option<Point>,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Damage(pub i32);
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Weapon;
\ No newline at end of file
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Weapon;
+
+// Energy is gained at the rate of an entity's speed, and every action costs
+// ACTION_COST of it, so a speed of 200 acts twice as often as NORMAL_SPEED.
+pub const NORMAL_SPEED: i32 = 100;
+pub const ACTION_COST: i32 = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Speed(pub i32);
+
+impl Speed {
+    // The speed with a haste or slow effect applied. Nothing stops entirely.
+    pub fn effective(self, effect: Option<&SpeedEffect>) -> i32 {
+        let speed = match effect {
+            Some(effect) if effect.turns > 0 => self.0 * effect.percent / 100,
+            _ => self.0,
+        };
+        speed.max(1)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Energy(pub i32);
+
+impl Energy {
+    // What is left once an action has been paid for.
+    pub fn spent(self) -> Self {
+        Self(self.0 - ACTION_COST)
+    }
+
+    // A monster with nothing to do pays nothing, but waits for time to pass
+    // before it's asked again, and banks no extra turns while it stands idle.
+    pub fn idle(self) -> Self {
+        Self(self.0.min(ACTION_COST - 1))
+    }
+}
+
+// Haste or slow, as a percentage of normal speed, for a number of turns.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpeedEffect {
+    pub percent: i32,
+    pub turns: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesHaste {
+    pub turns: i32,
+}
+
+// Slows every enemy the user can see, or every enemy caught in the area
+// when the item is aimed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesSlow {
+    pub turns: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesDamage {
+    pub amount: i32,
+}
+
+// An item aimed at a tile up to `range` away that the user can see, rather
+// than used on themselves.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ranged {
+    pub range: i32,
+}
+
+// An aimed item whose effect spreads to everything within `radius` of where
+// it lands.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AreaOfEffect {
+    pub radius: i32,
+}
+
+// Hit points regained with time, one every `turns` turns, for the player and
+// monsters alike.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Regeneration {
+    pub turns: i32,
+    pub progress: i32,
+}
+
+impl Regeneration {
+    pub fn new(turns: i32) -> Self {
+        Self { turns, progress: 0 }
+    }
+}
+
+// How many turns in a row the player has waited.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Resting {
+    pub turns: i32,
+}
+
+// A monster that has heard the player, and comes for them even when it
+// can't see them.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hunting;
@@ -0,0 +1,29 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// A level the player has left, kept exactly as it was: its map, with the
+// tiles the player has seen, and every entity on it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredLevel {
+    pub map: Map,
+    pub theme: ThemeKind,
+    pub entities: Vec<SavedEntity>,
+}
+
+// Every level of the dungeon the player has visited, other than the one
+// they are on now.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Dungeon {
+    levels: BTreeMap<u32, StoredLevel>,
+}
+
+impl Dungeon {
+    pub fn store(&mut self, level: u32, stored: StoredLevel) {
+        self.levels.insert(level, stored);
+    }
+
+    pub fn take(&mut self, level: u32) -> Option<StoredLevel> {
+        self.levels.remove(&level)
+    }
+}
@@ -0,0 +1,64 @@
+use crate::prelude::*;
+
+// An entity as it was when an event happened. The entity may be gone by the
+// time the event is read, so its name comes along with it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventEntity {
+    pub entity: Entity,
+    pub name: String,
+    pub is_player: bool,
+}
+
+impl EventEntity {
+    pub fn of<S: EntityStore>(ecs: &S, entity: Entity) -> Self {
+        let entry = ecs.entry_ref(entity).ok();
+        let name = entry.as_ref()
+            .and_then(|e| e.get_component::<Name>().ok().map(|name| name.0.clone()));
+        let is_player = entry.as_ref()
+            .is_some_and(|e| e.get_component::<Player>().is_ok());
+        Self {
+            entity,
+            name: name.unwrap_or_else(|| if is_player { "you" } else { "something" }.to_string()),
+            is_player,
+        }
+    }
+}
+
+// Something that happened in the game, for any system that wants to react
+// to it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    DamageDealt { attacker: EventEntity, victim: EventEntity, amount: i32 },
+    EntityDied { victim: EventEntity, killer: EventEntity },
+    ItemPickedUp { by: EventEntity, item: EventEntity },
+    ItemUsed { by: EventEntity, item: EventEntity },
+    SpeedChanged { entity: EventEntity, percent: i32, turns: i32 },
+    MapRevealed,
+    LevelEntered { level: u32, going_down: bool },
+    TileRevealed { pos: Point },
+    // A monster heard the player resting and is on its way.
+    RestDisturbed { monster: EventEntity },
+    Victory,
+}
+
+// The events sent since the schedules last ran. Systems send them while a
+// schedule runs; the subscriber systems at the end of every schedule read
+// them, and they are cleared once the schedule has finished.
+#[derive(Default)]
+pub struct Events {
+    queue: Vec<GameEvent>,
+}
+
+impl Events {
+    pub fn send(&mut self, event: GameEvent) {
+        self.queue.push(event);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &GameEvent> {
+        self.queue.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
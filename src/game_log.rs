@@ -0,0 +1,65 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Older messages are dropped once the log grows past this.
+const MAX_LOG_ENTRIES: usize = 1000;
+
+// How many of the latest messages the HUD shows.
+pub const HUD_LOG_LINES: usize = 5;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub text: String,
+    pub color: (u8, u8, u8),
+}
+
+// Everything that has happened in the run, in the order it happened, for
+// the HUD and the message history.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GameLog {
+    entries: Vec<LogEntry>,
+}
+
+impl GameLog {
+    pub fn add<S: Into<String>>(&mut self, text: S, color: (u8, u8, u8)) {
+        self.entries.push(LogEntry { text: text.into(), color });
+        if self.entries.len() > MAX_LOG_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    // A warning meant for whoever is working on the game's data files. It is
+    // printed as well, and only logged in debug builds.
+    pub fn warn<S: Into<String>>(&mut self, text: S) {
+        let text = text.into();
+        println!("Warning: {text}");
+        if cfg!(debug_assertions) {
+            self.add(format!("Warning: {text}"), MAGENTA);
+        }
+    }
+
+    pub fn latest(&self, count: usize) -> &[LogEntry] {
+        &self.entries[self.entries.len().saturating_sub(count)..]
+    }
+
+    // Draw the whole history on the text console, newest at the bottom,
+    // scrolled back by the given number of lines.
+    pub fn draw_history(&self, ctx: &mut BTerm, scroll: usize) {
+        ctx.set_active_console(2);
+        ctx.print_color_centered(1, YELLOW, BLACK, "Message Log");
+        ctx.print_color_centered(SCREEN_HEIGHT*2 - 1, GRAY, BLACK,
+            "Up/Down, Page Up/Page Down: scroll  Escape: back");
+
+        let rows = SCREEN_HEIGHT as usize * 2 - 5;
+        let end = self.entries.len().saturating_sub(scroll);
+        let start = end.saturating_sub(rows);
+        for (y, entry) in (3..).zip(&self.entries[start..end]) {
+            ctx.print_color(2, y, entry.color, BLACK, &entry.text);
+        }
+    }
+
+    // The furthest the history can be scrolled back.
+    pub fn max_scroll(&self) -> usize {
+        self.entries.len().saturating_sub(SCREEN_HEIGHT as usize * 2 - 5)
+    }
+}
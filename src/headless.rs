@@ -0,0 +1,254 @@
+use crate::prelude::*;
+use crate::State;
+use std::fs;
+use std::io::Read;
+
+// A scripted stand-in for a key press, so runs can be driven from a file
+// or from code instead of a live terminal.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    Move(Point),
+    // Moving into an enemy attacks it, so this is the same key as a move.
+    Attack(Point),
+    PickUp,
+    UseItem(usize),
+    Wait,
+    // Use or put away an item being aimed. Moves move its cursor meanwhile.
+    Confirm,
+    Cancel,
+}
+
+impl Action {
+    // Parse a single line of a script, such as "left", "get" or "use 2".
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let action = match words.next()? {
+            "left" => Action::Move(Point::new(-1, 0)),
+            "right" => Action::Move(Point::new(1, 0)),
+            "up" => Action::Move(Point::new(0, -1)),
+            "down" => Action::Move(Point::new(0, 1)),
+            "up-left" => Action::Move(Point::new(-1, -1)),
+            "up-right" => Action::Move(Point::new(1, -1)),
+            "down-left" => Action::Move(Point::new(-1, 1)),
+            "down-right" => Action::Move(Point::new(1, 1)),
+            "get" => Action::PickUp,
+            "wait" => Action::Wait,
+            "confirm" => Action::Confirm,
+            "cancel" => Action::Cancel,
+            "use" => Action::UseItem(words.next()?.parse().ok()?),
+            _ => return None,
+        };
+        Some(action)
+    }
+
+    // The key player_input expects for this action.
+    fn key(self) -> Option<VirtualKeyCode> {
+        match self {
+            Action::Move(delta) | Action::Attack(delta) => direction_key(delta),
+            Action::PickUp => Some(VirtualKeyCode::G),
+            Action::Wait => Some(VirtualKeyCode::Period),
+            Action::Confirm => Some(VirtualKeyCode::Return),
+            Action::Cancel => Some(VirtualKeyCode::Escape),
+            Action::UseItem(n) => [
+                VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3,
+                VirtualKeyCode::Key4, VirtualKeyCode::Key5, VirtualKeyCode::Key6,
+                VirtualKeyCode::Key7, VirtualKeyCode::Key8, VirtualKeyCode::Key9,
+            ].get(n.checked_sub(1)?).copied(),
+        }
+    }
+}
+
+// Runs the same World, Resources and schedules as the windowed game, but
+// takes its input from scripted actions and never opens a window.
+pub struct HeadlessRunner {
+    state: State,
+    turns: usize,
+}
+
+impl HeadlessRunner {
+    pub fn new(seed: Seed, settings: GameSettings) -> Self {
+        Self { state: State::new(seed, settings), turns: 0 }
+    }
+
+    pub fn turn_state(&self) -> TurnState {
+        *self.state.resources.get::<TurnState>().unwrap()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.is_finished()
+    }
+
+    // Confirm and Cancel only mean something while aiming an item or looking
+    // around. Anywhere else their keys would end the player's turn, so they
+    // are ignored instead.
+    pub fn act(&mut self, action: Action) -> TurnState {
+        let pointing = matches!(self.turn_state(), TurnState::Targeting | TurnState::Looking);
+        if matches!(action, Action::Confirm | Action::Cancel) && !pointing {
+            return self.turn_state();
+        }
+        self.press(action.key())
+    }
+
+    // Feed a key to the game, then keep stepping until it is waiting for
+    // input again or has ended.
+    pub fn press(&mut self, key: Option<VirtualKeyCode>) -> TurnState {
+        self.state.play_turn(key);
+        self.turns += 1;
+
+        // The render systems still queue their draw batches; with no
+        // terminal to consume them they would pile up.
+        clear_command_buffer().expect("Unable to clear the draw buffer");
+        self.turn_state()
+    }
+
+    // Simulated runs shouldn't fill the morgue file and high-score table.
+    pub fn keep_records(&mut self, keep: bool) {
+        self.state.keep_records = keep;
+    }
+
+    pub fn stats(&self) -> RunStats {
+        self.state.resources.get::<RunStats>().unwrap().clone()
+    }
+
+    pub fn turns(&self) -> usize {
+        self.turns
+    }
+
+    pub fn player_health(&self) -> Health {
+        *<&Health>::query()
+            .filter(component::<Player>())
+            .iter(&self.state.ecs)
+            .next()
+            .unwrap()
+    }
+
+    pub fn report(&self, seed: Seed) {
+        let health = self.player_health();
+        println!("Seed: {}", seed.0);
+        println!("Turns: {}", self.turns);
+        println!("Dungeon Level: {}", self.map_level() + 1);
+        println!("Health: {} / {}", health.current, health.max);
+        println!("State: {:?}", self.turn_state());
+    }
+
+    // What the player knows, for an agent to decide on.
+    pub fn view(&self) -> AgentView {
+        AgentView::of(&self.state.ecs, &self.state.resources)
+    }
+
+    pub fn map_level(&self) -> u32 {
+        <&Player>::query()
+            .iter(&self.state.ecs)
+            .next()
+            .unwrap()
+            .map_level
+    }
+}
+
+// Play back a recorded replay and report how the run ended.
+pub fn run_replay(replay: Replay) {
+    let mut playback = Playback::new(replay);
+    let mut runner = HeadlessRunner::new(playback.seed(), playback.settings());
+    while !playback.is_finished() && !runner.is_finished() {
+        runner.press(playback.next_key());
+    }
+    runner.report(playback.seed());
+}
+
+// Play a script of actions, one per line, read from the given file or from
+// standard input, and report how the run ended.
+pub fn run_script(seed: Seed, settings: GameSettings, path: Option<&str>) {
+    let script = if let Some(path) = path {
+        fs::read_to_string(path).expect("Unable to read script")
+    } else {
+        let mut script = String::new();
+        std::io::stdin().read_to_string(&mut script).expect("Unable to read script");
+        script
+    };
+
+    let mut runner = HeadlessRunner::new(seed, settings);
+    for (line_number, line) in script.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(action) = Action::parse(line) {
+            runner.act(action);
+        } else {
+            println!("Warning: line {}: unknown action [{}]", line_number + 1, line);
+        }
+        if runner.is_finished() {
+            break;
+        }
+    }
+
+    runner.report(seed);
+}
+
+// Let an agent play a whole run, giving up after max_turns, and report how
+// it ended.
+pub fn run_agent(seed: Seed, settings: GameSettings, agent: &mut dyn Agent, max_turns: usize) {
+    let mut runner = HeadlessRunner::new(seed, settings);
+    while !runner.is_finished() && runner.turns < max_turns {
+        let action = agent.act(&runner.view());
+        runner.act(action);
+    }
+    runner.report(seed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancelling_with_nothing_aimed_takes_no_turn() {
+        let mut runner = HeadlessRunner::new(Seed(3), GameSettings::default());
+        runner.keep_records(false);
+        for action in [Action::Cancel, Action::Confirm] {
+            assert_eq!(runner.act(action), TurnState::AwaitingInput);
+            assert_eq!(runner.stats().turns, 0);
+        }
+        runner.act(Action::Wait);
+        assert_eq!(runner.stats().turns, 1);
+    }
+
+    // The level the player is on, apart from the player and whatever they
+    // carry: its tiles, which of them have been seen and what stands where.
+    fn level(runner: &HeadlessRunner) -> (Vec<TileType>, Vec<bool>, Vec<String>) {
+        let map = runner.state.resources.get::<Map>().unwrap();
+        let mut entities: Vec<String> = <(Option<&Name>, &Point, Option<&Health>, Option<&Energy>)>::query()
+            .filter(!component::<Player>())
+            .iter(&runner.state.ecs)
+            .map(|(name, pos, health, energy)| format!("{:?} {:?} {:?} {:?}",
+                name.map(|name| &name.0), pos,
+                health.map(|health| (health.current, health.max)), energy.map(|energy| energy.0)))
+            .collect();
+        entities.sort();
+        (map.tiles.clone(), map.revealed_tiles.clone(), entities)
+    }
+
+    fn take_stairs(runner: &mut HeadlessRunner, down: bool) {
+        runner.state.resources.insert(if down { TurnState::NextLevel } else { TurnState::PreviousLevel });
+        runner.press(None);
+    }
+
+    #[test]
+    fn a_level_left_behind_is_found_as_it_was() {
+        let mut runner = HeadlessRunner::new(Seed(3), GameSettings::default());
+        runner.keep_records(false);
+        let mut explorer = Explorer::default();
+        for _ in 0..40 {
+            let action = explorer.act(&runner.view());
+            runner.act(action);
+        }
+        assert_eq!(runner.map_level(), 0);
+        let first = level(&runner);
+
+        take_stairs(&mut runner, true);
+        assert_eq!(runner.map_level(), 1);
+        assert!(level(&runner) != first);
+
+        take_stairs(&mut runner, false);
+        assert_eq!(runner.map_level(), 0);
+        assert!(level(&runner) == first);
+    }
+}
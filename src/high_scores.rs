@@ -0,0 +1,173 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HIGH_SCORE_FILE: &str = "highscores.ron";
+const MAX_HIGH_SCORES: usize = 10;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: i64,
+    pub seed: Seed,
+    pub date: String,
+    pub deepest_level: u32,
+    pub kills: u32,
+    pub turns: usize,
+    pub amulet: bool,
+    pub cause_of_death: Option<String>,
+}
+
+impl HighScore {
+    // Recovering the amulet outweighs everything else, then every level
+    // reached and every kill counts, and a long run costs a little.
+    pub fn new(stats: &RunStats, seed: Seed, amulet: bool) -> Self {
+        let kills: u32 = stats.kills.values().sum();
+        let score = if amulet { 1000 } else { 0 }
+            + i64::from(stats.deepest_level + 1) * 100
+            + i64::from(kills) * 10
+            - i64::try_from(stats.turns / 10).unwrap_or(i64::MAX);
+        Self {
+            score: score.max(0),
+            seed,
+            date: today(),
+            deepest_level: stats.deepest_level,
+            kills,
+            turns: stats.turns,
+            amulet,
+            cause_of_death: stats.cause_of_death.clone(),
+        }
+    }
+
+    pub fn fate(&self) -> String {
+        if self.amulet {
+            "Recovered the amulet".to_string()
+        } else {
+            format!("Slain by {}", self.cause_of_death.as_deref().unwrap_or("something"))
+        }
+    }
+}
+
+// Today's date as YYYY-MM-DD, in UTC.
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let days = i64::try_from(secs / 86_400).unwrap_or(0);
+
+    // Convert days since 1970-01-01 into a civil date, counting in 400
+    // year eras that start on March 1st.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+// The best runs so far, highest score first.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        if !Path::new(HIGH_SCORE_FILE).exists() {
+            return Self::default();
+        }
+        let loaded = File::open(HIGH_SCORE_FILE)
+            .map_err(|e| e.to_string())
+            .and_then(|file| ron::de::from_reader(file).map_err(|e| e.to_string()));
+        loaded.unwrap_or_else(|e| {
+            println!("Warning: unable to load {HIGH_SCORE_FILE}: {e}");
+            Self::default()
+        })
+    }
+
+    // Add a run to the table, returning its rank if it made the cut. A
+    // run goes below every earlier run with the same score, so ties keep
+    // their order.
+    pub fn record(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self.entries
+            .iter()
+            .take_while(|e| e.score >= entry.score)
+            .count();
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+
+    pub fn save(&self) {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::new())
+            .expect("Unable to serialize high scores");
+        if let Err(e) = atomic_write(HIGH_SCORE_FILE, &contents) {
+            println!("Warning: unable to write {HIGH_SCORE_FILE}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A run told apart from the others by its seed.
+    fn run(score: i64, seed: u64) -> HighScore {
+        HighScore {
+            score,
+            seed: Seed(seed),
+            date: String::new(),
+            deepest_level: 0,
+            kills: 0,
+            turns: 0,
+            amulet: false,
+            cause_of_death: None,
+        }
+    }
+
+    fn seeds(scores: &HighScores) -> Vec<u64> {
+        scores.entries.iter().map(|e| e.seed.0).collect()
+    }
+
+    #[test]
+    fn runs_are_ranked_highest_first_and_ties_keep_their_order() {
+        let mut scores = HighScores::default();
+        assert_eq!(scores.record(run(100, 1)), Some(0));
+        assert_eq!(scores.record(run(300, 2)), Some(0));
+        assert_eq!(scores.record(run(100, 3)), Some(2));
+        assert_eq!(scores.record(run(200, 4)), Some(1));
+        assert_eq!(seeds(&scores), vec![2, 4, 1, 3]);
+    }
+
+    #[test]
+    fn only_the_best_runs_are_kept() {
+        let mut scores = HighScores::default();
+        for seed in 0..MAX_HIGH_SCORES as u64 {
+            scores.record(run(50, seed));
+        }
+        assert_eq!(scores.record(run(50, 99)), None);
+        assert_eq!(scores.record(run(10, 98)), None);
+        assert_eq!(scores.record(run(60, 97)), Some(0));
+        assert_eq!(scores.entries.len(), MAX_HIGH_SCORES);
+        assert!(!seeds(&scores).contains(&(MAX_HIGH_SCORES as u64 - 1)));
+    }
+
+    #[test]
+    fn the_amulet_outweighs_depth_and_kills() {
+        let mut stats = RunStats { deepest_level: 2, turns: 50, ..RunStats::default() };
+        stats.kills.insert("Goblin".to_string(), 3);
+        let slain = HighScore::new(&stats, Seed(1), false);
+        assert_eq!(slain.score, 300 + 30 - 5);
+        let won = HighScore::new(&RunStats::default(), Seed(1), true);
+        assert!(won.score > slain.score);
+    }
+}
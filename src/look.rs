@@ -0,0 +1,30 @@
+use crate::prelude::*;
+
+// The cursor of look mode, which examines the map from the keyboard.
+#[derive(Clone, Copy, Debug)]
+pub struct Look {
+    pub cursor: Point,
+}
+
+impl Look {
+    pub fn new(cursor: Point) -> Self {
+        Self { cursor }
+    }
+
+    // Move the cursor a step, sliding along the edge of the view, or of the
+    // map if it doesn't fill the view.
+    pub fn move_by(&mut self, delta: Point, camera: &Camera, map: &Map) {
+        self.cursor = camera.clamp(self.cursor + delta, map);
+    }
+
+    // Jump to the target after the one under the cursor, or to the first
+    // if the cursor isn't on one, wrapping around at the end.
+    pub fn next_target(&mut self, targets: &[Point]) {
+        let next = targets.iter()
+            .position(|pt| *pt == self.cursor)
+            .map_or(0, |i| (i + 1) % targets.len());
+        if let Some(target) = targets.get(next) {
+            self.cursor = *target;
+        }
+    }
+}
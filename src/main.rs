@@ -7,6 +7,27 @@ mod map_builder;
 mod systems;
 mod camera;
 mod turn_state;
+mod seed;
+mod save;
+mod headless;
+mod replay;
+mod menu;
+mod options;
+mod dungeon;
+mod campaign;
+mod morgue;
+mod high_scores;
+mod game_log;
+mod events;
+mod settings;
+mod cli;
+mod wizard;
+mod agent;
+mod simulation;
+mod achievements;
+mod travel;
+mod look;
+mod targeting;
 
 mod prelude {
     pub use bracket_lib::prelude::*;
@@ -24,6 +45,25 @@ mod prelude {
     pub use crate::map_builder::*;
     pub use crate::camera::*;
     pub use crate::turn_state::*;
+    pub use crate::seed::*;
+    pub use crate::save::*;
+    pub use crate::replay::*;
+    pub use crate::menu::*;
+    pub use crate::options::*;
+    pub use crate::dungeon::*;
+    pub use crate::campaign::*;
+    pub use crate::morgue::*;
+    pub use crate::high_scores::*;
+    pub use crate::game_log::*;
+    pub use crate::events::*;
+    pub use crate::settings::*;
+    pub use crate::cli::*;
+    pub use crate::wizard::*;
+    pub use crate::agent::*;
+    pub use crate::achievements::*;
+    pub use crate::travel::*;
+    pub use crate::look::*;
+    pub use crate::targeting::*;
 }
 
 use prelude::*;
@@ -34,93 +74,566 @@ struct State {
     resources: Resources,
     input_systems: Schedule,
     player_systems: Schedule,
-    monster_systems: Schedule
+    monster_systems: Schedule,
+    look_systems: Schedule,
+    targeting_systems: Schedule,
+    recording: Option<Replay>,
+    playback: Option<Playback>,
+    options: Options,
+    menu_selection: usize,
+    summary: Option<Vec<String>>,
+    high_scores: HighScores,
+    log_scroll: usize,
+    settings: GameSettings,
+    // Whether runs start with wizard mode switched on, which only
+    // `--wizard` asks for.
+    wizard: bool,
+    // Where the wizard's spawn menu puts what is chosen, and what it offers.
+    wizard_target: Point,
+    wizard_templates: Vec<String>,
+    // Whether a finished run is written to the morgue file and entered in
+    // the high-score table.
+    keep_records: bool,
 }
 
 impl State {
-    fn new() -> Self {
-        let mut ecs = World::default();
-        let mut resources = Resources::default();
-        let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
-        spawn_player(&mut ecs, map_builder.player_start);
-        //spawn_amulet_of_yala(&mut ecs, map_builder.amulet_start);
-        let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-        map_builder.map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(
-            &mut ecs,
-            &mut rng,
-            0, &map_builder.monster_spawns
-        );
-        resources.insert(map_builder.map);
-        resources.insert(Camera::new(map_builder.player_start));
-        resources.insert(TurnState::AwaitingInput);
-        resources.insert(map_builder.theme);
-        Self {
-            ecs,
-            resources,
+    fn new(seed: Seed, settings: GameSettings) -> Self {
+        let mut state = Self {
+            ecs: World::default(),
+            resources: Resources::default(),
             input_systems: build_input_scheduler(),
             player_systems: build_player_scheduler(),
-            monster_systems: build_monster_scheduler()
-        }
+            monster_systems: build_monster_scheduler(),
+            look_systems: build_look_scheduler(),
+            targeting_systems: build_targeting_scheduler(),
+            recording: None,
+            playback: None,
+            options: Options::load(),
+            menu_selection: 0,
+            summary: None,
+            high_scores: HighScores::load(),
+            log_scroll: 0,
+            settings,
+            wizard: false,
+            wizard_target: Point::zero(),
+            wizard_templates: Vec::new(),
+            keep_records: true,
+        };
+        state.reset_game_state(seed);
+        state
     }
 
-    fn reset_game_state(&mut self) {
+    // Every random decision in a run is drawn from a single generator,
+    // stored as a resource, so the same seed and the same inputs always
+    // produce the same game.
+    fn reset_game_state(&mut self, seed: Seed) {
+        self.playback = None;
+        self.summary = None;
         self.ecs = World::default();
         self.resources = Resources::default();
-        let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
-        spawn_player(&mut self.ecs, map_builder.player_start);
-        //spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
-        let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-        map_builder.map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(
-            &mut self.ecs,
-            &mut rng,
-            0, &map_builder.monster_spawns
-        );
-        self.resources.insert(map_builder.map);
-        self.resources.insert(Camera::new(map_builder.player_start));
+        let mut rng = RandomNumberGenerator::seeded(seed.0);
+        let mut log = GameLog::default();
+        log.add("You enter the dungeon in search of the Amulet of Yala.", YELLOW);
+        self.resources.insert(log);
+        self.resources.insert(self.settings.clone());
+        let campaign = Campaign::load().unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        });
+        self.resources.insert(campaign);
+        self.resources.insert(Templates::load(self.settings.templates_path()));
+        let start_level = self.settings.start_level;
+        let (map, theme, player_start) = self.generate_level(&mut rng, start_level, false);
+        spawn_player(&mut self.ecs, player_start);
+        <&mut Player>::query()
+            .iter_mut(&mut self.ecs)
+            .for_each(|player| player.map_level = start_level);
+        self.resources.insert(self.camera(player_start, &map));
+        self.resources.insert(map);
         self.resources.insert(TurnState::AwaitingInput);
-        self.resources.insert(map_builder.theme);
+        self.resources.insert(theme);
+        self.resources.insert(rng);
+        self.resources.insert(seed);
+        self.resources.insert(Dungeon::default());
+        self.resources.insert(RunStats { deepest_level: start_level, ..RunStats::default() });
+        self.resources.insert(Events::default());
+        self.resources.insert(Wizard { enabled: self.wizard, ..Wizard::default() });
+        self.resources.insert(Achievements::load());
+        self.resources.insert(AchievementProgress::new(start_level));
+        self.resources.insert(Travel::default());
+        self.resources.insert(Look::new(player_start));
+        self.resources.insert(None::<Targeting>);
     }
 
-    fn game_over(&mut self, ctx: &mut BTerm) {
-        ctx.set_active_console(2);
-        ctx.print_color_centered(2, RED, BLACK, "Your quest has ended.");
-        ctx.print_color_centered(4, WHITE, BLACK, 
-            "Slain by a monster, your hero's journey has come to a premature end.");
-        ctx.print_color_centered(5, WHITE, BLACK, 
-            "The Amulet of Yala remains unclaimed, and your home town is not saved.");
-        ctx.print_color_centered(8, YELLOW, BLACK, 
-            "Don't worry, you can always try again with a new hero.");
-        ctx.print_color_centered(9, GREEN, BLACK, "Press 1 to play again.");
+    // A camera on the player, set up as the options ask.
+    fn camera(&self, player_pos: Point, map: &Map) -> Camera {
+        Camera::new(player_pos, map, self.options.zoom, self.options.camera_dead_zone)
+    }
+
+    // Point the camera in play at the player again, after the options for
+    // it have changed.
+    fn reset_camera(&mut self) {
+        let player_pos = <&Point>::query()
+            .filter(component::<Player>())
+            .iter(&self.ecs)
+            .next()
+            .copied();
+        if let Some(player_pos) = player_pos {
+            let camera = self.camera(player_pos, &self.resources.get::<Map>().unwrap());
+            self.resources.insert(camera);
+        }
+    }
+
+    // The map and entity consoles hold one cell per tile the camera shows,
+    // so zooming out draws the tiles smaller. The mouse position is read
+    // from the map console, so it always maps onto a tile.
+    #[allow(clippy::cast_sign_loss)]
+    fn fit_consoles_to_camera(&self, ctx: &mut BTerm) {
+        let camera = *self.resources.get::<Camera>().unwrap();
+        let size = (camera.width() as u32, camera.height() as u32);
+        for console in 0 ..= 1 {
+            ctx.set_active_console(console);
+            if ctx.get_char_size() != size {
+                ctx.set_char_size(size.0, size.1);
+            }
+        }
+    }
+
+    // Build a level the player hasn't visited yet, as the campaign describes
+    // it, and spawn everything on it. Returns where the player arrives: by
+    // stairs leading back up, unless the run starts here.
+    fn generate_level(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        map_level: u32,
+        stairs_up: bool
+    ) -> (Map, Box<dyn MapTheme>, Point) {
+        let settings = self.resources.get::<GameSettings>().unwrap();
+        let level = settings.apply(self.resources.get::<Campaign>().unwrap().level(map_level));
+        let templates = self.resources.get::<Templates>().unwrap();
+        let mut log = self.resources.get_mut::<GameLog>().unwrap();
+        let mut map_builder = MapBuilder::new(rng, &level, &mut log);
+
+        let mut player_start = map_builder.player_start;
+        if stairs_up {
+            let stairs_idx = map_builder.map.point2d_to_index(player_start);
+            map_builder.map.tiles[stairs_idx] = TileType::UpStairs;
+            player_start = map_builder.map.landing_point(player_start);
+        }
+
+        // The exit takes the far end of the level; the amulet and the boss
+        // stand beside it if there is one.
+        let far_end = map_builder.amulet_start;
+        let beside_exit = map_builder.map.landing_point(far_end);
+        let guarded = if level.exit {
+            let exit_idx = map_builder.map.point2d_to_index(far_end);
+            map_builder.map.tiles[exit_idx] = TileType::Exit;
+            beside_exit
+        } else {
+            far_end
+        };
+        if level.amulet {
+            spawn_amulet_of_yala(&mut self.ecs, guarded);
+        }
+        if let Some(boss) = &level.boss {
+            spawn_boss(&mut self.ecs, &templates, boss, guarded, &mut log);
+        }
+
+        spawn_level(&mut self.ecs, rng, &templates, &level.template_levels,
+            &map_builder.monster_spawns, &mut log);
+        map_builder.map.steps = settings.step_rule();
+        (map_builder.map, map_builder.theme, player_start)
+    }
+
+    // The player preferences, each entry toggling one.
+    fn options_menu(&self) -> Menu {
+        Menu::new("Options", YELLOW)
+            .entry(
+                format!("Record replays: {}",
+                    if self.options.record_replays { "On" } else { "Off" }),
+                MenuAction::ToggleRecordReplays
+            )
+            .entry(
+                format!("Zoom: {}",
+                    if self.options.zoom == Zoom::Near { "Near" } else { "Far" }),
+                MenuAction::ToggleZoom
+            )
+            .entry(
+                format!("Camera: {}",
+                    if self.options.camera_dead_zone { "Dead zone" } else { "Centred" }),
+                MenuAction::ToggleCameraDeadZone
+            )
+            .entry("Back", MenuAction::MainMenu)
+            .back(MenuAction::MainMenu)
+    }
+
+    // The menu shown for the current state, if any. Every screen outside of
+    // the dungeon is a menu, so tick handles them all the same way.
+    fn menu(&self) -> Option<Menu> {
+        let current_state = *self.resources.get::<TurnState>().unwrap();
+        let menu = match current_state {
+            TurnState::MainMenu => {
+                let mut menu = Menu::new("Rusty Crawler", YELLOW)
+                    .line("Yet Another Lost Amulet", WHITE)
+                    .entry("New Game", MenuAction::NewGame);
+                if has_saved_game() {
+                    menu = menu.entry("Continue", MenuAction::Continue);
+                }
+                menu.entry("Options", MenuAction::Options)
+                    .entry("High Scores", MenuAction::HighScores)
+                    .entry("Achievements", MenuAction::Achievements)
+                    .entry("Quit", MenuAction::Quit)
+            }
+            TurnState::PauseMenu => Menu::new("Paused", YELLOW)
+                .entry("Resume", MenuAction::Resume)
+                .entry("Save and return to the main menu", MenuAction::SaveAndExit)
+                .entry("Save and quit", MenuAction::SaveAndQuit)
+                .back(MenuAction::Resume),
+            TurnState::Options => self.options_menu(),
+            TurnState::HighScores => {
+                let menu = Menu::new("High Scores", YELLOW);
+                let menu = if self.high_scores.entries.is_empty() {
+                    menu.line("No high scores yet.", WHITE)
+                } else {
+                    let header = format!("{:>2}  {:>5}  {:>5}  {:>5}  {:>5}  {:>20}  {:<10}  {:<30}",
+                        "#", "Score", "Depth", "Kills", "Turns", "Seed", "Date", "Fate");
+                    self.high_scores.entries
+                        .iter()
+                        .enumerate()
+                        .fold(menu.line(header, YELLOW), |menu, (rank, entry)| menu.line(
+                            format!("{:>2}  {:>5}  {:>5}  {:>5}  {:>5}  {:>20}  {:<10}  {:<30}",
+                                rank + 1, entry.score, entry.deepest_level + 1, entry.kills,
+                                entry.turns, entry.seed.0, entry.date, entry.fate()),
+                            WHITE
+                        ))
+                };
+                menu.entry("Back", MenuAction::MainMenu)
+                    .back(MenuAction::MainMenu)
+            }
+            TurnState::Achievements => {
+                let achievements = self.resources.get::<Achievements>().unwrap();
+                let unlocked = |a: &Achievement| achievements.unlocked.contains(&a.name);
+                let count = achievements.list.iter().filter(|a| unlocked(a)).count();
+                achievements.list
+                    .iter()
+                    .fold(
+                        Menu::new("Achievements", YELLOW)
+                            .line(format!("{count} of {} unlocked", achievements.list.len()), WHITE)
+                            .line("", BLACK),
+                        |menu, a| if unlocked(a) {
+                            menu.line(format!("{:<24} {:<50}", a.name, a.description), GOLD)
+                        } else {
+                            menu.line(format!("{:<24} {:<50}", "Locked", a.description), GREY)
+                        }
+                    )
+                    .entry("Back", MenuAction::MainMenu)
+                    .back(MenuAction::MainMenu)
+            }
+            TurnState::WizardSpawn => self.wizard_templates
+                .iter()
+                .enumerate()
+                .fold(Menu::new("Spawn what?", MAGENTA), |menu, (n, name)|
+                    menu.entry(name.clone(), MenuAction::WizardSpawn(n))
+                )
+                .back(MenuAction::Resume),
+            TurnState::GameOver | TurnState::Victory => {
+                let menu = if current_state == TurnState::GameOver {
+                    Menu::new("Your quest has ended.", RED)
+                        .line("Slain by a monster, your hero's journey has come to a premature end.",
+                            WHITE)
+                        .line("The Amulet of Yala remains unclaimed, and your home town is not saved.",
+                            WHITE)
+                        .line("", BLACK)
+                        .line("Don't worry, you can always try again with a new hero.", YELLOW)
+                } else {
+                    Menu::new("You have won!", GREEN)
+                        .line("You put on the Amulet of Yala and feel its power course through your veins.",
+                            WHITE)
+                        .line("Your town is saved, and you can return to your normal life.", WHITE)
+                };
+                self.summary
+                    .iter()
+                    .flatten()
+                    .fold(menu.line("", BLACK), |menu, line| menu.line(line.clone(), GREY))
+                    .entry("Play again", MenuAction::NewGame)
+                    .entry("Main menu", MenuAction::MainMenu)
+            }
+            _ => return None,
+        };
+        Some(menu)
+    }
+
+    fn perform(&mut self, action: MenuAction, ctx: &mut BTerm) {
+        self.menu_selection = 0;
+        match action {
+            MenuAction::NewGame => self.new_game(Seed::random()),
+            MenuAction::Continue => self.continue_game(),
+            MenuAction::Options => self.resources.insert(TurnState::Options),
+            MenuAction::HighScores => self.resources.insert(TurnState::HighScores),
+            MenuAction::Achievements => self.resources.insert(TurnState::Achievements),
+            MenuAction::Quit => ctx.quit(),
+            MenuAction::Resume => self.resources.insert(TurnState::AwaitingInput),
+            MenuAction::SaveAndExit => {
+                self.save();
+                self.resources.insert(TurnState::MainMenu);
+            }
+            MenuAction::SaveAndQuit => self.save_and_quit(ctx),
+            MenuAction::MainMenu => {
+                self.finish_recording();
+                self.playback = None;
+                self.resources.insert(TurnState::MainMenu);
+            }
+            MenuAction::ToggleRecordReplays => {
+                self.options.record_replays = !self.options.record_replays;
+                self.options.save();
+            }
+            MenuAction::ToggleZoom => {
+                self.options.zoom = self.options.zoom.toggle();
+                self.options.save();
+                self.reset_camera();
+            }
+            MenuAction::ToggleCameraDeadZone => {
+                self.options.camera_dead_zone = !self.options.camera_dead_zone;
+                self.options.save();
+                self.reset_camera();
+            }
+            MenuAction::WizardSpawn(n) => {
+                let templates = self.resources.get::<Templates>().unwrap();
+                let mut log = self.resources.get_mut::<GameLog>().unwrap();
+                templates.spawn_named(&mut self.ecs, &self.wizard_templates[n],
+                    self.wizard_target, &mut log);
+                log.add(format!("Wizard: you conjure a {}.", self.wizard_templates[n]), MAGENTA);
+                drop(log);
+                drop(templates);
+                self.resources.insert(TurnState::AwaitingInput);
+            }
+        }
+    }
 
-        if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
+    // Start a fresh run, recording it if replays are switched on.
+    fn new_game(&mut self, seed: Seed) {
+        self.finish_recording();
+        self.reset_game_state(seed);
+        if self.options.record_replays {
+            self.recording = Some(Replay::new(seed, self.settings.clone()));
+        }
+    }
+
+    fn finish_recording(&mut self) {
+        if let Some(replay) = self.recording.take() {
+            replay.save(REPLAY_FILE);
+        }
+    }
+
+    // Replace the current game with the saved one. Schedules are rebuilt so
+    // that no system carries state over from the game being replaced.
+    fn continue_game(&mut self) {
+        match load_game() {
+            Ok((ecs, resources)) => self.resume(ecs, resources),
+            Err(e) => {
+                println!("Warning: unable to load the saved game: {e}");
+            }
+        }
+    }
+
+    fn resume(&mut self, ecs: World, resources: Resources) {
+        // A replay only holds the keys pressed since the seed, so a game
+        // resumed part way through can't be recorded.
+        self.finish_recording();
+        self.ecs = ecs;
+        self.resources = resources;
+        self.summary = None;
+        self.input_systems = build_input_scheduler();
+        self.player_systems = build_player_scheduler();
+        self.monster_systems = build_monster_scheduler();
+        self.look_systems = build_look_scheduler();
+        self.targeting_systems = build_targeting_scheduler();
+        // The view follows the options, not the ones it was saved with.
+        self.reset_camera();
+    }
+
+    // Save the game in progress, if there is one. A game being replayed is
+    // never saved.
+    fn save(&mut self) {
+        self.finish_recording();
+        let current_state = *self.resources.get::<TurnState>().unwrap();
+        let in_progress = matches!(current_state,
+            TurnState::AwaitingInput | TurnState::PlayerTurn | TurnState::MonsterTurn
+                | TurnState::NextLevel | TurnState::PreviousLevel | TurnState::PauseMenu
+                | TurnState::MessageLog | TurnState::WizardSpawn | TurnState::Looking
+                | TurnState::Targeting);
+        if in_progress && self.playback.is_none() {
+            if matches!(current_state,
+                TurnState::PauseMenu | TurnState::MessageLog | TurnState::WizardSpawn
+                    | TurnState::Looking | TurnState::Targeting)
+            {
+                self.resources.insert(TurnState::AwaitingInput);
+                self.resources.insert(None::<Targeting>);
+            }
+            if let Err(e) = save_game(&self.ecs, &self.resources) {
+                println!("Warning: unable to save the game: {e}");
+            }
         }
     }
 
-    fn victory(&mut self, ctx: &mut BTerm) {
+    fn save_and_quit(&mut self, ctx: &mut BTerm) {
+        self.save();
+        ctx.quit();
+    }
+
+    // Play back the recorded keys at the pace set by the playback controls,
+    // then draw the game as it now stands.
+    fn replay_tick(&mut self, ctx: &mut BTerm) {
+        let mut playback = self.playback.take().unwrap();
+        playback.control(ctx.key);
+        for _ in 0 .. playback.turns_this_frame() {
+            if playback.is_finished() || self.is_finished() {
+                break;
+            }
+            self.play_turn(playback.next_key());
+        }
+
+        // Fast-forwarding plays several turns in one frame, only the last
+        // of them should be drawn.
+        clear_command_buffer().expect("Unable to clear the draw buffer");
+        self.step(None, Point::from_tuple(ctx.mouse_pos()));
+
         ctx.set_active_console(2);
-        ctx.print_color_centered(2, GREEN, BLACK, "You have won!");
-        ctx.print_color_centered(4, WHITE, BLACK, 
-            "You put on the Amulet of Yala and feel its power course through your veins.");
-        ctx.print_color_centered(5, WHITE, BLACK, 
-            "Your town is saved, and you can return to your normal life.");
-        ctx.print_color_centered(7, GREEN, BLACK, "Press 1 to play again.");
-        if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
+        ctx.print_color(1, SCREEN_HEIGHT*2 - 2, YELLOW, BLACK, playback.status());
+        ctx.print_color(1, SCREEN_HEIGHT*2 - 1, GRAY, BLACK,
+            "Space: pause  .: step  F: fast-forward  Escape: quit");
+        self.playback = Some(playback);
+    }
+
+    // The full message history, scrolled with the cursor keys.
+    fn message_log_tick(&mut self, ctx: &mut BTerm) {
+        let log = self.resources.get::<GameLog>().unwrap();
+        let page = SCREEN_HEIGHT as usize;
+        self.log_scroll = match ctx.key {
+            Some(VirtualKeyCode::Up) => self.log_scroll + 1,
+            Some(VirtualKeyCode::Down) => self.log_scroll.saturating_sub(1),
+            Some(VirtualKeyCode::PageUp) => self.log_scroll + page,
+            Some(VirtualKeyCode::PageDown) => self.log_scroll.saturating_sub(page),
+            _ => self.log_scroll,
+        }.min(log.max_scroll());
+        log.draw_history(ctx, self.log_scroll);
+        drop(log);
+        if matches!(ctx.key, Some(VirtualKeyCode::Escape | VirtualKeyCode::P)) {
+            self.resources.insert(TurnState::AwaitingInput);
+        }
+    }
+
+    // Carry out a wizard mode command. Cheats act outside of the turn order,
+    // so a replay can't reproduce them: the recording is dropped, and the
+    // run no longer scores.
+    fn wizard_command(&mut self, command: WizardCommand, map_pos: Point) {
+        let mut log = self.resources.get_mut::<GameLog>().unwrap();
+        let mut wizard = self.resources.get_mut::<Wizard>().unwrap();
+        if command.is_cheat() {
+            self.resources.get_mut::<RunStats>().unwrap().wizard = true;
+            if self.recording.take().is_some() {
+                log.add("Wizard: this run is no longer being recorded.", MAGENTA);
+            }
+        }
+        match command {
+            WizardCommand::Toggle => {
+                wizard.enabled = !wizard.enabled;
+                log.add(format!("Wizard mode {}.", if wizard.enabled { "on" } else { "off" }),
+                    MAGENTA);
+            }
+            WizardCommand::RevealMap => {
+                self.resources.get_mut::<Map>().unwrap()
+                    .revealed_tiles
+                    .iter_mut()
+                    .for_each(|revealed| *revealed = true);
+                log.add("Wizard: the whole level is revealed.", MAGENTA);
+            }
+            WizardCommand::ToggleInvulnerable => {
+                wizard.invulnerable = !wizard.invulnerable;
+                log.add(format!("Wizard: you are {}.",
+                    if wizard.invulnerable { "invulnerable" } else { "vulnerable again" }),
+                    MAGENTA);
+            }
+            WizardCommand::ShowEntities => wizard.show_entities = !wizard.show_entities,
+            WizardCommand::Spawn => {
+                if let Err(why) = self.wizard_spot(map_pos) {
+                    log.add(format!("Wizard: nothing can be conjured there, {why}."), MAGENTA);
+                    return;
+                }
+                let mut names: Vec<String> = Vec::new();
+                for template in &self.resources.get::<Templates>().unwrap().entities {
+                    if !names.contains(&template.name) {
+                        names.push(template.name.clone());
+                    }
+                }
+                self.wizard_templates = names;
+                self.wizard_target = map_pos;
+                self.menu_selection = 0;
+                *self.resources.get_mut::<TurnState>().unwrap() = TurnState::WizardSpawn;
+            }
+            WizardCommand::Teleport => {
+                if let Err(why) = self.wizard_spot(map_pos) {
+                    log.add(format!("Wizard: you can't teleport there, {why}."), MAGENTA);
+                } else {
+                    let map = self.resources.get::<Map>().unwrap();
+                    <(&mut Point, &mut FieldOfView)>::query()
+                        .filter(component::<Player>())
+                        .iter_mut(&mut self.ecs)
+                        .for_each(|(pos, fov)| {
+                            *pos = map_pos;
+                            fov.is_dirty = true;
+                        });
+                    self.resources.get_mut::<Camera>().unwrap().on_player_move(map_pos, &map);
+                    log.add("Wizard: you teleport.", MAGENTA);
+                }
+            }
+            // The last level has no exit, and nothing below it to sink to.
+            WizardCommand::NextLevel => {
+                if self.resources.get::<Map>().unwrap().find_tile(TileType::Exit).is_some() {
+                    log.add("Wizard: you sink through the floor.", MAGENTA);
+                    *self.resources.get_mut::<TurnState>().unwrap() = TurnState::NextLevel;
+                } else {
+                    log.add("Wizard: there is no level below this one.", MAGENTA);
+                }
+            }
+        }
+    }
+
+    // Whether the wizard can put something at a tile: it has to be open
+    // ground, with no creature already standing on it.
+    fn wizard_spot(&self, pos: Point) -> Result<(), &'static str> {
+        let map = self.resources.get::<Map>().unwrap();
+        let occupied = <&Point>::query()
+            .filter(component::<Health>())
+            .iter(&self.ecs)
+            .any(|other| *other == pos);
+        if !map.can_enter_tile(pos) {
+            Err("it isn't open ground")
+        } else if occupied {
+            Err("something is already standing there")
+        } else {
+            Ok(())
         }
     }
 
     fn advance_level(&mut self) {
+        self.change_level(true);
+    }
+
+    fn return_to_previous_level(&mut self) {
+        self.change_level(false);
+    }
+
+    // Move the player one level up or down the dungeon. The level being left
+    // is stored in the Dungeon resource, with everything on it, so that the
+    // player finds it as they left it if they come back.
+    fn change_level(&mut self, going_down: bool) {
 
         // Find the player.
-        let player_entity = *<Entity>::query()
-            .filter(component::<Player>())
-            .iter(&mut self.ecs)
-            .nth(0)
+        let (player_entity, current_level) = <(Entity, &Player)>::query()
+            .iter(&self.ecs)
+            .map(|(entity, player)| (*entity, player.map_level))
+            .next()
             .unwrap();
 
         // Mark entities to keep.
@@ -135,12 +648,23 @@ impl State {
             }
         );
 
+        // Store the level being left, with the other entities on it.
+        let left_behind: Vec<Entity> = Entity::query()
+            .iter(&self.ecs)
+            .filter(|e| !entities_to_keep.contains(e))
+            .copied()
+            .collect();
+        let mut dungeon = self.resources.remove::<Dungeon>().unwrap();
+        dungeon.store(current_level, StoredLevel {
+            map: self.resources.remove::<Map>().unwrap(),
+            theme: self.resources.get::<Box<dyn MapTheme>>().unwrap().kind(),
+            entities: snapshot_entities(&self.ecs, &left_behind),
+        });
+
         // Remove the other entities.
-        let mut cb = CommandBuffer::new(&mut self.ecs);
-        for e in Entity::query().iter(&self.ecs) {
-            if !entities_to_keep.contains(e) {
-                cb.remove(*e);
-            }
+        let mut cb = CommandBuffer::new(&self.ecs);
+        for e in &left_behind {
+            cb.remove(*e);
         }
         cb.flush(&mut self.ecs);
 
@@ -149,79 +673,288 @@ impl State {
             .iter_mut(&mut self.ecs)
             .for_each(|fov| fov.is_dirty = true);
 
-        // Create a new map
-        let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
+        // Return to a level that has been visited before, or create a new one.
+        let map_level = if going_down { current_level + 1 } else { current_level - 1 };
+        let (map, theme, player_start) = if let Some(level) = dungeon.take(map_level) {
+            restore_entities(&mut self.ecs, level.entities);
+            let stairs = level.map
+                .find_tile(if going_down { TileType::UpStairs } else { TileType::Exit })
+                .expect("Stored level has no stairs");
+            let player_start = level.map.landing_point(stairs);
+            (level.map, level.theme.build(), player_start)
+        } else {
+            let mut rng = self.resources.remove::<RandomNumberGenerator>().unwrap();
+            let level = self.generate_level(&mut rng, map_level, true);
+            self.resources.insert(rng);
+            level
+        };
 
         // Place the player in the new map
-        let mut map_level = 0;
         <(&mut Player, &mut Point)>::query()
             .iter_mut(&mut self.ecs)
             .for_each(|(player, pos)| {
-                player.map_level += 1;
-                map_level = player.map_level;
-                pos.x = map_builder.player_start.x;
-                pos.y = map_builder.player_start.y;
+                player.map_level = map_level;
+                *pos = player_start;
             }
         );
-        
-        // Spawn the amulet or a staircase
-        if map_level == 2 {
-            spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
-        } else {
-            let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-            map_builder.map.tiles[exit_idx] = TileType::Exit;
-        }
 
         // Update resources.
-        spawn_level(&mut self.ecs, &mut rng, map_level as usize, &map_builder.monster_spawns);
-        self.resources.insert(map_builder.map);
-        self.resources.insert(Camera::new(map_builder.player_start));
+        self.resources.insert(self.camera(player_start, &map));
+        self.resources.insert(map);
         self.resources.insert(TurnState::AwaitingInput);
-        self.resources.insert(map_builder.theme);
+        self.resources.insert(theme);
+        self.resources.insert(dungeon);
+        self.resources.get_mut::<Events>().unwrap()
+            .send(GameEvent::LevelEntered { level: map_level, going_down });
+    }
+
+    // Enter the run that just ended in the high-score table.
+    fn record_high_score(&mut self) {
+        if self.resources.get::<RunStats>().unwrap().wizard {
+            return;
+        }
+        let entry = HighScore::new(
+            &self.resources.get::<RunStats>().unwrap(),
+            *self.resources.get::<Seed>().unwrap(),
+            *self.resources.get::<TurnState>().unwrap() == TurnState::Victory,
+        );
+        if self.high_scores.record(entry).is_some() {
+            self.high_scores.save();
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(*self.resources.get::<TurnState>().unwrap(),
+            TurnState::GameOver | TurnState::Victory)
+    }
+
+    // Feed one key to the game, then keep stepping until it is waiting for
+    // input again, including a target for an item, or has ended.
+    fn play_turn(&mut self, key: Option<VirtualKeyCode>) {
+        self.step(key, Point::zero());
+        while !self.is_finished()
+            && !matches!(*self.resources.get::<TurnState>().unwrap(),
+                TurnState::AwaitingInput | TurnState::Targeting)
+        {
+            self.step(None, Point::zero());
+        }
+    }
+
+    // Advance the game by one tick with the given input. This is all of the
+    // game logic and needs no terminal, so the headless runner shares it;
+    // screens that wait on the player are handled by the caller.
+    fn step(&mut self, key: Option<VirtualKeyCode>, mouse_pos: Point) {
+        self.resources.insert(key);
+        self.resources.insert(mouse_pos);
+        let current_state = *self.resources.get::<TurnState>().unwrap();
+        match current_state {
+            TurnState::AwaitingInput => {
+                self.input_systems.execute(&mut self.ecs, &mut self.resources);
+
+                // Travelling presses keys of its own, which are recorded
+                // along with the player's.
+                let key = *self.resources.get::<Option<VirtualKeyCode>>().unwrap();
+                if let (Some(replay), Some(key)) = (&mut self.recording, key) {
+                    replay.record(key);
+                }
+                self.resources.get_mut::<Events>().unwrap().clear();
+            }
+            TurnState::Looking => {
+                self.look_systems.execute(&mut self.ecs, &mut self.resources);
+            }
+            TurnState::Targeting => {
+                self.targeting_systems.execute(&mut self.ecs, &mut self.resources);
+
+                // Where an item was aimed is part of the game, so the keys
+                // that aimed it are recorded too.
+                if let (Some(replay), Some(key)) = (&mut self.recording, key) {
+                    replay.record(key);
+                }
+            }
+            TurnState::PlayerTurn => {
+                self.player_systems.execute(&mut self.ecs, &mut self.resources);
+                self.resources.get_mut::<Events>().unwrap().clear();
+            }
+            TurnState::MonsterTurn => {
+                self.monster_systems.execute(&mut self.ecs, &mut self.resources);
+                self.resources.get_mut::<Events>().unwrap().clear();
+            }
+            TurnState::NextLevel => {
+                self.advance_level();
+            }
+            TurnState::PreviousLevel => {
+                self.return_to_previous_level();
+            }
+            TurnState::GameOver | TurnState::Victory | TurnState::MainMenu
+                | TurnState::PauseMenu | TurnState::Options | TurnState::HighScores
+                | TurnState::MessageLog | TurnState::WizardSpawn
+                | TurnState::Achievements => {}
+        }
+
+        let mut achievements = self.resources.get_mut::<Achievements>().unwrap();
+        if achievements.changed {
+            achievements.changed = false;
+            if self.playback.is_none() && self.keep_records {
+                achievements.save();
+            }
+        }
+        drop(achievements);
+
+        // Sum the run up as soon as it ends. A replay only shows it again,
+        // and simulated runs keep no records.
+        if self.summary.is_none() && self.is_finished() {
+            let summary = run_summary(&self.ecs, &self.resources);
+            if self.playback.is_none() && self.keep_records {
+                write_morgue(&summary);
+                self.record_high_score();
+            }
+            self.summary = Some(summary);
+        }
     }
 }
 
 impl GameState for State {
     fn tick(&mut self, ctx: &mut BTerm) {
+        self.fit_consoles_to_camera(ctx);
         ctx.set_active_console(0);
         ctx.cls();
         ctx.set_active_console(1);
         ctx.cls();
         ctx.set_active_console(2);
         ctx.cls();
-        self.resources.insert(ctx.key);
         ctx.set_active_console(0);
-        self.resources.insert(Point::from_tuple(ctx.mouse_pos()));
-        let current_state = self.resources.get::<TurnState>().unwrap().clone();
-        match current_state {
-            TurnState::AwaitingInput => self.input_systems.execute(&mut self.ecs, &mut self.resources),
-            TurnState::PlayerTurn => {
-                self.player_systems.execute(&mut self.ecs, &mut self.resources);
-            }
-            TurnState::MonsterTurn => {
-                self.monster_systems.execute(&mut self.ecs, &mut self.resources)
-            }
-            TurnState::GameOver => {
-                self.game_over(ctx);
+        let current_state = *self.resources.get::<TurnState>().unwrap();
+        let wizard_command = if current_state == TurnState::AwaitingInput {
+            self.resources.get::<Wizard>().unwrap().command(ctx.key)
+        } else {
+            None
+        };
+
+        // Closing the window mid-game saves it, the same as pressing Escape.
+        if ctx.quitting {
+            self.save_and_quit(ctx);
+            return;
+        }
+
+        if let Some(menu) = self.menu() {
+            if let Some(action) = menu.handle_input(ctx.key, &mut self.menu_selection) {
+                self.perform(action, ctx);
+            } else {
+                menu.draw(ctx, self.menu_selection);
             }
-            TurnState::Victory => {
-                self.victory(ctx);
-            },
-            TurnState::NextLevel => {
-                self.advance_level();
+        } else if self.playback.is_some() {
+            self.replay_tick(ctx);
+        } else if current_state == TurnState::MessageLog {
+            self.message_log_tick(ctx);
+        } else if current_state == TurnState::AwaitingInput
+            && ctx.key == Some(VirtualKeyCode::Escape)
+        {
+            self.resources.insert(TurnState::PauseMenu);
+        } else if current_state == TurnState::AwaitingInput
+            && ctx.key == Some(VirtualKeyCode::P)
+        {
+            self.log_scroll = 0;
+            self.resources.insert(TurnState::MessageLog);
+        } else if current_state == TurnState::AwaitingInput
+            && ctx.key == Some(VirtualKeyCode::E)
+        {
+            // Looking starts on the player and takes no turn.
+            let player_pos = *<&Point>::query()
+                .filter(component::<Player>())
+                .iter(&self.ecs)
+                .next()
+                .unwrap();
+            self.resources.insert(Look::new(player_pos));
+            self.resources.insert(TurnState::Looking);
+            self.step(None, Point::from_tuple(ctx.mouse_pos()));
+        } else if current_state == TurnState::AwaitingInput
+            && ctx.key == Some(VirtualKeyCode::Z)
+        {
+            // Zooming only changes the view, so it takes no turn.
+            self.options.zoom = self.options.zoom.toggle();
+            self.options.save();
+            self.reset_camera();
+            self.step(None, Point::from_tuple(ctx.mouse_pos()));
+        } else if let Some(command) = wizard_command {
+            let map_pos = Point::from_tuple(ctx.mouse_pos())
+                + self.resources.get::<Camera>().unwrap().offset();
+            self.wizard_command(command, map_pos);
+        } else {
+            let mouse_pos = Point::from_tuple(ctx.mouse_pos());
+            if ctx.left_click && current_state == TurnState::AwaitingInput {
+                let map_pos = mouse_pos + self.resources.get::<Camera>().unwrap().offset();
+                self.resources.get_mut::<Travel>().unwrap().requested = Some(map_pos);
             }
+            self.step(ctx.key, mouse_pos);
         }
         render_draw_buffer(ctx).expect("Render error");
     }
 }
 
 fn main() -> BError {
+    let cli = Cli::parse();
+    let replay = cli.replay.as_deref().map(|path| Replay::load(path).unwrap_or_else(|e| {
+        eprintln!("Error: unable to load the replay {path}: {e}");
+        std::process::exit(1);
+    }));
+    if let Some(runs) = cli.simulate {
+        simulation::run_simulations(
+            cli.seed.unwrap_or_else(Seed::random),
+            runs,
+            &cli.settings,
+            cli.agent.as_deref().unwrap_or("explorer"),
+            cli.max_turns.unwrap_or(10_000),
+            cli.report.as_deref()
+        );
+        return Ok(());
+    }
+    if cli.headless {
+        match (replay, &cli.agent) {
+            (Some(replay), _) => headless::run_replay(replay),
+            (None, Some(name)) => headless::run_agent(
+                cli.seed.unwrap_or_else(Seed::random),
+                cli.settings,
+                agent_by_name(name).unwrap().as_mut(),
+                cli.max_turns.unwrap_or(10_000)
+            ),
+            (None, None) => headless::run_script(
+                cli.seed.unwrap_or_else(Seed::random),
+                cli.settings,
+                cli.script.as_deref()
+            ),
+        }
+        return Ok(());
+    }
+
+    let state = if let Some(replay) = replay {
+        let playback = Playback::new(replay);
+        let mut state = State::new(playback.seed(), playback.settings());
+        state.playback = Some(playback);
+        state
+    } else if cli.seed.is_some() || cli.settings != GameSettings::default() {
+        // Asking for a particular dungeon skips the main menu.
+        let mut state = State::new(Seed::random(), cli.settings);
+        state.wizard = cli.wizard;
+        state.new_game(cli.seed.unwrap_or_else(Seed::random));
+        state
+    } else {
+        let mut state = State::new(Seed::random(), cli.settings);
+        state.wizard = cli.wizard;
+        state.resources.insert(TurnState::MainMenu);
+        state
+    };
+
+    // The window is DISPLAY_WIDTH by DISPLAY_HEIGHT tiles, so a window size
+    // sets how large each tile is drawn.
+    let (tile_width, tile_height) = cli.window.map_or((32, 32), |(width, height)| (
+        (width / DISPLAY_WIDTH.unsigned_abs()).max(1),
+        (height / DISPLAY_HEIGHT.unsigned_abs()).max(1),
+    ));
     let context = BTermBuilder::new()
         .with_title("Rusty Crawler")
         .with_fps_cap(30.0)
         .with_dimensions(DISPLAY_WIDTH, DISPLAY_HEIGHT)
-        .with_tile_dimensions(32, 32)
+        .with_tile_dimensions(tile_width, tile_height)
         .with_resource_path("resources/")
         .with_font("dungeonfont.png", 32, 32)
         .with_font("terminal8x8.png", 8, 8)
@@ -230,5 +963,7 @@ fn main() -> BError {
         .with_simple_console_no_bg(SCREEN_WIDTH*2, SCREEN_HEIGHT*2, "terminal8x8.png")
         .build()?;
 
-    main_loop(context, State::new())
+    main_loop(context, state)
 }
+
+
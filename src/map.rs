@@ -1,21 +1,70 @@
 use crate::prelude::*;
-const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, PartialEq)]
+// The size of a level the campaign doesn't give one.
+pub const MAP_WIDTH: i32 = 80;
+pub const MAP_HEIGHT: i32 = 50;
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
     Exit,
+    UpStairs,
+}
+
+// Every step there is, orthogonal ones first. The step rule says which of
+// them a move may take.
+pub const STEPS: [Point; 8] = [
+    Point { x: -1, y: 0 },
+    Point { x: 1, y: 0 },
+    Point { x: 0, y: -1 },
+    Point { x: 0, y: 1 },
+    Point { x: -1, y: -1 },
+    Point { x: 1, y: -1 },
+    Point { x: -1, y: 1 },
+    Point { x: 1, y: 1 },
+];
+
+// Which of its neighbours a tile can be left for.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StepRule {
+    // Levels are laid out with orthogonal steps only, so whatever is placed
+    // on them can be reached however the run lets the player move.
+    Orthogonal,
+    EightWay,
+    // Diagonal steps are allowed, but not past the corner of a wall.
+    EightWayNoCornerCutting,
 }
 
+impl StepRule {
+    // Whether a step by `delta` is allowed, given which tiles can be
+    // entered. Orthogonal steps always are.
+    pub fn allows(self, loc: Point, delta: Point, can_enter: impl Fn(Point) -> bool) -> bool {
+        if delta.x == 0 || delta.y == 0 {
+            return true;
+        }
+        match self {
+            StepRule::Orthogonal => false,
+            StepRule::EightWay => true,
+            StepRule::EightWayNoCornerCutting =>
+                can_enter(loc + Point::new(delta.x, 0)) && can_enter(loc + Point::new(0, delta.y)),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Map {
+    pub width: i32,
+    pub height: i32,
     pub tiles: Vec<TileType>,
     pub revealed_tiles: Vec<bool>,
+    pub steps: StepRule,
 }
 
 impl Algorithm2D for Map {
     fn dimensions(&self) -> Point {
-        Point::new(SCREEN_WIDTH, SCREEN_HEIGHT)
+        Point::new(self.width, self.height)
     }
 
     fn in_bounds(&self, point: Point) -> bool {
@@ -23,49 +72,78 @@ impl Algorithm2D for Map {
     }
 }
 
-pub fn map_idx(x: i32, y: i32) -> usize {
-    ((y * SCREEN_WIDTH) + x) as usize
-}
-
 impl Map {
-    pub fn new() -> Self {
+    #[allow(clippy::cast_sign_loss)]
+    pub fn new(width: i32, height: i32) -> Self {
+        let num_tiles = (width * height) as usize;
         Self {
-            tiles: vec![TileType::Floor; NUM_TILES],
-            revealed_tiles: vec![false; NUM_TILES],
+            width,
+            height,
+            tiles: vec![TileType::Floor; num_tiles],
+            revealed_tiles: vec![false; num_tiles],
+            steps: StepRule::Orthogonal,
         }
     }
 
     // This function checks that the location specified in point 
     // is greater than zero on both the x and y axes, and that 
-    // it is less than the screen height and width.
+    // it is less than the map's height and width.
     pub fn in_bounds(&self, point: Point) ->bool {
-        point.x >= 0 && point.x < SCREEN_WIDTH
-            && point.y >= 0 && point.y < SCREEN_HEIGHT
+        point.x >= 0 && point.x < self.width
+            && point.y >= 0 && point.y < self.height
     }
 
     // Determine if the player can enter a tile.
     pub fn can_enter_tile(&self, point: Point) -> bool {
         self.in_bounds(point) && (
-            self.tiles[map_idx(point.x, point.y)] == TileType::Floor ||
-            self.tiles[map_idx(point.x, point.y)] == TileType::Exit
+            self.tiles[self.point2d_to_index(point)] == TileType::Floor ||
+            self.tiles[self.point2d_to_index(point)] == TileType::Exit ||
+            self.tiles[self.point2d_to_index(point)] == TileType::UpStairs
         ) 
     }
 
+    pub fn find_tile(&self, tile: TileType) -> Option<Point> {
+        self.tiles
+            .iter()
+            .position(|t| *t == tile)
+            .map(|idx| self.index_to_point2d(idx))
+    }
+
+    // A floor tile next to a staircase, where a player arriving by it is
+    // placed. Standing on the stairs would take them straight back.
+    pub fn landing_point(&self, stairs: Point) -> Point {
+        self.get_available_exits(self.point2d_to_index(stairs))
+            .iter()
+            .map(|(idx, _)| *idx)
+            .find(|idx| self.tiles[*idx] == TileType::Floor)
+            .map_or(stairs, |idx| self.index_to_point2d(idx))
+    }
+
     // Determine the tile's index coordinates. Indicate an error 
     // condition if the requested coordinates fall outside of the 
     // map boundaries.
     pub fn try_idx(&self, point: Point) -> Option<usize> {
-        if !self.in_bounds(point) {
-            None
+        if self.in_bounds(point) {
+            Some(self.point2d_to_index(point))
         } else {
-            Some(map_idx(point.x, point.y))
+            None
         }
     }
 
+    // Whether the step rule lets anything move from one tile to the other:
+    // they must be neighbours, and the second one open.
+    pub fn is_step(&self, from: Point, to: Point) -> bool {
+        let delta = to - from;
+        delta != Point::zero() && delta.x.abs() <= 1 && delta.y.abs() <= 1
+            && self.valid_exit(from, delta).is_some()
+    }
+
     fn valid_exit(&self, loc: Point, delta: Point) -> Option<usize> {
         let destination = loc + delta;
         if self.in_bounds(destination) {
-            if self.can_enter_tile(destination) {
+            if self.can_enter_tile(destination)
+                && self.steps.allows(loc, delta, |pt| self.can_enter_tile(pt))
+            {
                 let idx = self.point2d_to_index(destination);
                 Some(idx)
             } else {
@@ -83,16 +161,23 @@ impl BaseMap for Map {
         let location = self.index_to_point2d(idx);
 
         if let Some(idx) = self.valid_exit(location, Point::new(-1, 0)) {
-            exists.push((idx, 1.0))
+            exists.push((idx, 1.0));
         }
         if let Some(idx) = self.valid_exit(location, Point::new(1, 0)) {
-            exists.push((idx, 1.0))
+            exists.push((idx, 1.0));
         }
         if let Some(idx) = self.valid_exit(location, Point::new(0, -1)) {
-            exists.push((idx, 1.0))
+            exists.push((idx, 1.0));
         }
         if let Some(idx) = self.valid_exit(location, Point::new(0, 1)) {
-            exists.push((idx, 1.0))
+            exists.push((idx, 1.0));
+        }
+
+        // Diagonal steps cover more ground, and cost as much more.
+        for delta in [Point::new(-1, -1), Point::new(1, -1), Point::new(-1, 1), Point::new(1, 1)] {
+            if let Some(idx) = self.valid_exit(location, delta) {
+                exists.push((idx, std::f32::consts::SQRT_2));
+            }
         }
         exists
     }
@@ -106,6 +191,6 @@ impl BaseMap for Map {
     }
 
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx as usize] != TileType::Floor
+        self.tiles[idx] != TileType::Floor
     }
 }
\ No newline at end of file
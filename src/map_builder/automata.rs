@@ -4,6 +4,7 @@ use super::MapArchitect;
 pub struct CellularAutomataArchitect {}
 
 impl CellularAutomataArchitect {
+    #[allow(clippy::unused_self)]
     fn random_noise_map(
         &mut self,
         rng: &mut RandomNumberGenerator,
@@ -19,13 +20,14 @@ impl CellularAutomataArchitect {
         });
     }
 
+    #[allow(clippy::unused_self)]
     fn count_neighbors(&self, x: i32, y: i32, map: &Map) -> usize {
         let mut neighbors = 0;
         for iy in -1 ..= 1 {
             for ix in -1 ..= 1 {
 
                 // Don't count the current tile, only count its neighbors.
-                if !(ix==0 && iy ==0) && map.tiles[map_idx(x+ix, y+iy)] == TileType::Wall {
+                if !(ix==0 && iy ==0) && map.tiles[map.point2d_to_index(Point::new(x+ix, y+iy))] == TileType::Wall {
                     neighbors += 1;
                 }
             }
@@ -35,10 +37,10 @@ impl CellularAutomataArchitect {
 
     fn iteration(&mut self, map: &mut Map) {
         let mut new_tiles = map.tiles.clone();
-        for y in 1 .. SCREEN_HEIGHT -1 {
-            for x in 1 .. SCREEN_WIDTH -1 {
+        for y in 1 .. map.height -1 {
+            for x in 1 .. map.width -1 {
                 let neighbors = self.count_neighbors(x, y, map);
-                let idx = map_idx(x, y);
+                let idx = map.point2d_to_index(Point::new(x, y));
                 if neighbors > 4 || neighbors == 0 {
                     new_tiles[idx] = TileType::Wall;
                 } else {
@@ -49,10 +51,11 @@ impl CellularAutomataArchitect {
         map.tiles = new_tiles;
     }
 
+    #[allow(clippy::unused_self)]
     fn find_start(&self, map: &Map) -> Point {
 
         // Store the center of the map in a Point.
-        let center = Point::new(SCREEN_WIDTH/2, SCREEN_HEIGHT/2);
+        let center = Point::new(map.width/2, map.height/2);
         let closest_point = map.tiles
 
             // Iterate all of the map tiles.
@@ -75,7 +78,7 @@ impl CellularAutomataArchitect {
             // min_by() finds the lowest value in an iterator set and allows you to specify the
             // comparison technique.
             .min_by(|(_, distance), (_, distance2)|
-                distance.partial_cmp(&distance2).unwrap()
+                distance.partial_cmp(distance2).unwrap()
             )
             .map(|(idx, _)| idx)
             .unwrap();
@@ -84,9 +87,9 @@ impl CellularAutomataArchitect {
 }
 
 impl MapArchitect for CellularAutomataArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+    fn new(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> MapBuilder {
         let mut mb = MapBuilder{
-            map: Map::new(),
+            map: Map::new(width, height),
             rooms: Vec::new(),
             monster_spawns: Vec::new(),
             player_start: Point::zero(),
@@ -98,7 +101,7 @@ impl MapArchitect for CellularAutomataArchitect {
             self.iteration(&mut mb.map);
         }
         let start = self.find_start(&mb.map);
-        mb.monster_spawns = mb.spawn_monsters(&start, rng);
+        mb.monster_spawns = mb.spawn_monsters(start, rng);
         mb.player_start = start;
         mb.amulet_start = mb.find_most_distant();
         mb
@@ -3,19 +3,18 @@ use super::MapArchitect;
 
 // How far a miner can stumble before passing out.
 const STAGGER_DISTANCE: usize = 400;
-const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
-const DESIRED_FLOOR: usize = NUM_TILES / 3;
 
 pub struct DrunkardsWalkArchitect {}
 
 impl DrunkardsWalkArchitect {
+    #[allow(clippy::unused_self)]
     fn drunkard(
         &mut self,
-        start: &Point,
+        start: Point,
         rng: &mut RandomNumberGenerator,
         map: &mut Map
     ) {
-        let mut drunkard_pos = start.clone();
+        let mut drunkard_pos = start;
         let mut distance_staggered = 0;
 
         loop {
@@ -45,9 +44,9 @@ impl DrunkardsWalkArchitect {
 }
 
 impl MapArchitect for DrunkardsWalkArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+    fn new(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> MapBuilder {
         let mut mb = MapBuilder{
-            map : Map::new(),
+            map : Map::new(width, height),
             rooms : Vec::new(),
             monster_spawns : Vec::new(),
             player_start : Point::zero(),
@@ -56,25 +55,28 @@ impl MapArchitect for DrunkardsWalkArchitect {
         };
 
         mb.fill(TileType::Wall);
-        let center = Point::new(SCREEN_WIDTH /2, SCREEN_HEIGHT/2);
+        let center = Point::new(width /2, height/2);
+
+        // Dig until a third of the map is floor.
+        let desired_floor = mb.map.tiles.len() / 3;
 
         // Start the digger at a random location on the map.
-        self.drunkard(&center, rng, &mut mb.map);
+        self.drunkard(center, rng, &mut mb.map);
         while mb.map.tiles.iter()
-            .filter(|t| **t == TileType::Floor).count() < DESIRED_FLOOR
+            .filter(|t| **t == TileType::Floor).count() < desired_floor
         {
             self.drunkard(
-                &Point::new(
-                    rng.range(0, SCREEN_WIDTH),
-                    rng.range(0, SCREEN_HEIGHT)
+                Point::new(
+                    rng.range(0, width),
+                    rng.range(0, height)
                 ),
                 rng,
                 &mut mb.map
             );
             let dijkstra_map = DijkstraMap::new(
-                SCREEN_WIDTH,
-                SCREEN_HEIGHT,
-                &vec![mb.map.point2d_to_index(center)],
+                width,
+                height,
+                &[mb.map.point2d_to_index(center)],
                 &mb.map,
                 1024.0
             );
@@ -89,7 +91,7 @@ impl MapArchitect for DrunkardsWalkArchitect {
                 // For each remaining entry in the iterator, convert the tile to a wall.
                 .for_each(|(idx, _)| mb.map.tiles[idx] = TileType::Wall);
         }
-        mb.monster_spawns = mb.spawn_monsters(&center, rng);
+        mb.monster_spawns = mb.spawn_monsters(center, rng);
         mb.player_start = center;
         mb.amulet_start = mb.find_most_distant();
         mb
@@ -5,16 +5,42 @@ mod drunkard;
 mod prefab;
 mod rooms;
 mod themes;
-use self::{drunkard::DrunkardsWalkArchitect, prefab::apply_prefab, rooms::RoomsArchitect, automata::CellularAutomataArchitect, themes::*};
+use self::{drunkard::DrunkardsWalkArchitect, prefab::apply_prefab, rooms::RoomsArchitect, automata::CellularAutomataArchitect};
+pub use self::themes::ThemeKind;
+use serde::{Deserialize, Serialize};
 
 pub trait MapTheme: Sync + Send {
     fn tile_to_render(&self, tile_type: TileType) -> FontCharType;
+    // A name and a description of the tile, for looking at it.
+    fn describe_tile(&self, tile_type: TileType) -> (&'static str, &'static str);
+    fn kind(&self) -> ThemeKind;
 }
 
 trait MapArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder;
+    #[allow(clippy::new_ret_no_self, clippy::wrong_self_convention)]
+    fn new(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> MapBuilder;
 }
 
+// Names an architect, so a level can ask for one from the campaign file.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ArchitectKind {
+    Drunkard,
+    Rooms,
+    Automata,
+}
+
+impl ArchitectKind {
+    fn build(self) -> Box<dyn MapArchitect> {
+        match self {
+            ArchitectKind::Drunkard => Box::new(DrunkardsWalkArchitect{}),
+            ArchitectKind::Rooms => Box::new(RoomsArchitect{}),
+            ArchitectKind::Automata => Box::new(CellularAutomataArchitect{}),
+        }
+    }
+}
+
+// Rooms on a map of the default size. Smaller and larger maps get as many
+// for their area.
 const NUM_ROOMS: usize = 20;
 
 pub struct MapBuilder {
@@ -27,20 +53,30 @@ pub struct MapBuilder {
 }
 
 impl MapBuilder {
-    pub fn new(rng: &mut RandomNumberGenerator) -> Self {
-        let mut architect: Box<dyn MapArchitect> = match rng.range(0, 3) {
-            0 => Box::new(DrunkardsWalkArchitect{}),
-            1 => Box::new(RoomsArchitect{}),
-            _ => Box::new(CellularAutomataArchitect{})
-        };
-        let mut mb = architect.new(rng);
-        apply_prefab(&mut mb, rng);
-
-        mb.theme = match rng.range(0, 2) {
-            0 => DungeonTheme::new(),
-            _ => ForestTheme::new(),
-        };
-        
+    // Build the map for one level of the campaign. An architect or theme
+    // the level leaves open is picked at random.
+    pub fn new(
+        rng: &mut RandomNumberGenerator,
+        level: &CampaignLevel,
+        log: &mut GameLog
+    ) -> Self {
+        let architect = level.architect.unwrap_or_else(|| match rng.range(0, 3) {
+            0 => ArchitectKind::Drunkard,
+            1 => ArchitectKind::Rooms,
+            _ => ArchitectKind::Automata,
+        });
+        let mut mb = architect.build().new(rng, level.width, level.height);
+
+        // The budget caps the architect's spawn points; the guards of a
+        // fortress prefab come on top of it.
+        mb.monster_spawns.truncate(level.monster_budget);
+        apply_prefab(&mut mb, rng, log);
+
+        mb.theme = level.theme.unwrap_or_else(|| match rng.range(0, 2) {
+            0 => ThemeKind::Dungeon,
+            _ => ThemeKind::Forest,
+        }).build();
+
         mb
     }
 
@@ -51,15 +87,19 @@ impl MapBuilder {
     // build_random_rooms() accepts a RandomNumberGenerator as a parameter.
     // It's a good idea to use the same PRNG throughout your map generation,
     // os if you re-use the same seed, you always get the same result.
+    #[allow(clippy::cast_sign_loss)]
     fn build_random_rooms(&mut self, rng: &mut RandomNumberGenerator) {
-        
-        // Keep generating rooms until there are NUM_ROOMS rooms on the map.
-        while self.rooms.len() < NUM_ROOMS {
+        let (width, height) = (self.map.width, self.map.height);
+        let num_rooms = (NUM_ROOMS * (width * height) as usize
+            / (MAP_WIDTH * MAP_HEIGHT) as usize).max(2);
+
+        // Keep generating rooms until there are num_rooms rooms on the map.
+        while self.rooms.len() < num_rooms {
 
             // Generates a randomly positioned room with random sizes.
             let room = Rect::with_size(
-                rng.range(1, SCREEN_WIDTH - 10), 
-                rng.range(1, SCREEN_HEIGHT -10), 
+                rng.range(1, width - 10), 
+                rng.range(1, height -10), 
                 rng.range(2, 10), 
                 rng.range(2, 10),
             );
@@ -67,7 +107,7 @@ impl MapBuilder {
             // Test the new room against each placed room, and flag it as
             // overlapping if rooms intersect.
             let mut overlap = false;
-            for r in self.rooms.iter() {
+            for r in &self.rooms {
                 if r.intersect(&room) {
                     overlap = true;
                 }
@@ -78,14 +118,14 @@ impl MapBuilder {
                 // If they don't overlap, check that they are within
                 // the map boundaries and set their contents to floors.
                 room.for_each(|p| {
-                    if p.x > 0 && p.x < SCREEN_WIDTH && p.y > 0
-                        && p.y < SCREEN_HEIGHT 
+                    if p.x > 0 && p.x < width && p.y > 0
+                        && p.y < height 
                     {
-                        let idx = map_idx(p.x, p.y);
+                        let idx = self.map.point2d_to_index(p);
                         self.map.tiles[idx] = TileType::Floor;
                     }
                 });
-                self.rooms.push(room)
+                self.rooms.push(room);
             }
         }
     }
@@ -101,7 +141,7 @@ impl MapBuilder {
         // tunnel along the way.
         for y in min(y1, y2) ..= max(y1, y2) {
             if let Some(idx) = self.map.try_idx(Point::new(x,y)) {
-                self.map.tiles[idx as usize] = TileType::Floor;
+                self.map.tiles[idx] = TileType::Floor;
             }
         }
     }
@@ -112,7 +152,7 @@ impl MapBuilder {
         use std::cmp::{min, max};
         for x in min(x1,x2) ..= max(x1,x2) {
             if let Some(idx) = self.map.try_idx(Point::new(x,y)) {
-                self.map.tiles[idx as usize] = TileType::Floor;
+                self.map.tiles[idx] = TileType::Floor;
             }
         }
     }
@@ -130,7 +170,7 @@ impl MapBuilder {
         // sort_by() sends pairs of rooms to the closure. The closure receives these as 
         // a and b. a.center().x finds the x coordinate of room A. This is then compared 
         // via the cmp() function with the center of room B.
-        rooms.sort_by(|a,b| a.center().x.cmp(&b.center().x));
+        rooms.sort_by_key(|a| a.center().x);
         
         // enumerate() counts items in the iterator and includes them as the first entry
         // in a tuple. The (i, room) extracts the counter into the variable i. skip() allows
@@ -155,15 +195,15 @@ impl MapBuilder {
     }
 
     fn find_most_distant(&self) -> Point {
+        const UNREACHABLE: &f32 = &f32::MAX;
         let dijkstra_map = DijkstraMap::new(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
-            &vec![self.map.point2d_to_index(self.player_start)],
+            self.map.width,
+            self.map.height,
+            &[self.map.point2d_to_index(self.player_start)],
             &self.map,
             1024.0
         );
 
-        const UNREACHABLE: &f32 = &f32::MAX;
         self.map.index_to_point2d(
             dijkstra_map.map
                 .iter()
@@ -176,7 +216,7 @@ impl MapBuilder {
 
     fn spawn_monsters(
         &self, 
-        start: &Point, 
+        start: Point, 
         rng: &mut RandomNumberGenerator
     ) -> Vec<Point> {
         const NUM_MONSTERS : usize = 50;
@@ -189,18 +229,19 @@ impl MapBuilder {
             .filter(|(idx, t)|
                 **t == TileType::Floor &&
                     DistanceAlg::Pythagoras.distance2d(
-                        *start,
+                        start,
                         self.map.index_to_point2d(*idx)
                     ) > 10.0
             )
             .map(|(idx, _)| self.map.index_to_point2d(idx))
             .collect();
 
+        // A small map may not have room for them all.
         let mut spawns = Vec::new();
-        for _ in 0 .. NUM_MONSTERS {
+        for _ in 0 .. NUM_MONSTERS.min(spawnable_tiles.len()) {
             let target_index = rng.random_slice_index(&spawnable_tiles)
                 .unwrap();
-            spawns.push(spawnable_tiles[target_index].clone());
+            spawns.push(spawnable_tiles[target_index]);
             spawnable_tiles.remove(target_index);
         }
         spawns
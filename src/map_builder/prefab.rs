@@ -14,13 +14,13 @@ const FORTRESS : (&str, i32, i32) = ("
 ------------
 ", 12, 11);
 
-pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator, log: &mut GameLog) {
     let mut placement = None;
 
     let dijkstra_map = DijkstraMap::new(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
-        &vec![mb.map.point2d_to_index(mb.player_start)],
+        mb.map.width,
+        mb.map.height,
+        &[mb.map.point2d_to_index(mb.player_start)],
         &mb.map,
         1024.0
     );
@@ -28,8 +28,8 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
     let mut attempts = 0;// (1)
     while placement.is_none() && attempts < 10 {// (2)
         let dimensions = Rect::with_size(// (3)
-            rng.range(0, SCREEN_WIDTH - FORTRESS.1),
-            rng.range(0, SCREEN_HEIGHT - FORTRESS.2),
+            rng.range(0, mb.map.width - FORTRESS.1),
+            rng.range(0, mb.map.height - FORTRESS.2),
             FORTRESS.1,
             FORTRESS.2
         );
@@ -58,7 +58,7 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
         let mut i = 0;// (11)
         for ty in placement.y .. placement.y + FORTRESS.2 {// (12)
             for tx in placement.x .. placement.x + FORTRESS.1 {
-                let idx = map_idx(tx, ty);
+                let idx = mb.map.point2d_to_index(Point::new(tx, ty));
                 let c = string_vec[i];// (13)
                 match c {// (14)
                     'M' => {// (15)
@@ -67,7 +67,7 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
                     }
                     '-' => mb.map.tiles[idx] = TileType::Floor,// (16)
                     '#' => mb.map.tiles[idx] = TileType::Wall,
-                    _ => println!("No idea what to do with [{}]", c)// (17)
+                    _ => log.warn(format!("No idea what to do with [{c}]"))// (17)
                 }
                 i += 1;
             }
@@ -4,9 +4,9 @@ use super::MapArchitect;
 pub struct RoomsArchitect {}
 
 impl MapArchitect for RoomsArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+    fn new(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> MapBuilder {
         let mut mb = MapBuilder{
-            map : Map::new(),
+            map : Map::new(width, height),
             rooms: Vec::new(),
             monster_spawns : Vec::new(),
             player_start : Point::zero(),
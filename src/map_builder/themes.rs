@@ -1,8 +1,27 @@
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Identifies a theme without holding on to the boxed trait object, so the
+// choice can be written to disk and rebuilt later.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ThemeKind {
+    Dungeon,
+    Forest,
+}
+
+impl ThemeKind {
+    pub fn build(self) -> Box<dyn MapTheme> {
+        match self {
+            ThemeKind::Dungeon => DungeonTheme::new(),
+            ThemeKind::Forest => ForestTheme::new(),
+        }
+    }
+}
 
 pub struct DungeonTheme {}
 
 impl DungeonTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self{})
     }
@@ -14,13 +33,28 @@ impl MapTheme for DungeonTheme {
             TileType::Floor => to_cp437('.'),
             TileType::Wall => to_cp437('#'),
             TileType::Exit => to_cp437('>'),
+            TileType::UpStairs => to_cp437('<'),
+        }
+    }
+
+    fn describe_tile(&self, tile_type: TileType) -> (&'static str, &'static str) {
+        match tile_type {
+            TileType::Floor => ("Stone floor", "Worn flagstones, damp underfoot."),
+            TileType::Wall => ("Stone wall", "Rough-hewn rock, far too solid to dig through."),
+            TileType::Exit => ("Stairs down", "A staircase leading deeper into the dungeon."),
+            TileType::UpStairs => ("Stairs up", "A staircase back up to the level above."),
         }
     }
+
+    fn kind(&self) -> ThemeKind {
+        ThemeKind::Dungeon
+    }
 }
 
 pub struct ForestTheme {}
 
 impl ForestTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self{})
     }
@@ -32,6 +66,20 @@ impl MapTheme for ForestTheme {
             TileType::Floor => to_cp437(';'),
             TileType::Wall => to_cp437('"'),
             TileType::Exit => to_cp437('>'),
+            TileType::UpStairs => to_cp437('<'),
         }
     }
-}
\ No newline at end of file
+
+    fn describe_tile(&self, tile_type: TileType) -> (&'static str, &'static str) {
+        match tile_type {
+            TileType::Floor => ("Forest floor", "Moss and fallen leaves."),
+            TileType::Wall => ("Trees", "The trees grow too close together to squeeze between."),
+            TileType::Exit => ("Stairs down", "Steps cut into the earth, leading down into the dark."),
+            TileType::UpStairs => ("Stairs up", "Steps leading back up to the level above."),
+        }
+    }
+
+    fn kind(&self) -> ThemeKind {
+        ThemeKind::Forest
+    }
+}
@@ -0,0 +1,111 @@
+use crate::prelude::*;
+
+// What happens when a menu entry is chosen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MenuAction {
+    NewGame,
+    Continue,
+    Options,
+    HighScores,
+    Achievements,
+    Quit,
+    Resume,
+    SaveAndExit,
+    SaveAndQuit,
+    MainMenu,
+    ToggleRecordReplays,
+    ToggleZoom,
+    ToggleCameraDeadZone,
+    WizardSpawn(usize),
+}
+
+// A screen of text with a list of entries to choose from. Every screen
+// outside of the dungeon itself is a menu, so they all share the same
+// keyboard handling: cursor keys and Enter, or the entry's number.
+pub struct Menu {
+    title: (String, RGB),
+    lines: Vec<(String, RGB)>,
+    entries: Vec<(String, MenuAction)>,
+    back: Option<MenuAction>,
+}
+
+impl Menu {
+    pub fn new<S: Into<String>>(title: S, color: (u8, u8, u8)) -> Self {
+        Self {
+            title: (title.into(), RGB::named(color)),
+            lines: Vec::new(),
+            entries: Vec::new(),
+            back: None,
+        }
+    }
+
+    // A line of text shown between the title and the entries.
+    pub fn line<S: Into<String>>(mut self, text: S, color: (u8, u8, u8)) -> Self {
+        self.lines.push((text.into(), RGB::named(color)));
+        self
+    }
+
+    pub fn entry<S: Into<String>>(mut self, label: S, action: MenuAction) -> Self {
+        self.entries.push((label.into(), action));
+        self
+    }
+
+    // The action taken when Escape is pressed.
+    pub fn back(mut self, action: MenuAction) -> Self {
+        self.back = Some(action);
+        self
+    }
+
+    pub fn handle_input(
+        &self,
+        key: Option<VirtualKeyCode>,
+        selection: &mut usize
+    ) -> Option<MenuAction> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        *selection = (*selection).min(self.entries.len() - 1);
+        match key? {
+            VirtualKeyCode::Up => {
+                *selection = (*selection + self.entries.len() - 1) % self.entries.len();
+                None
+            }
+            VirtualKeyCode::Down => {
+                *selection = (*selection + 1) % self.entries.len();
+                None
+            }
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter | VirtualKeyCode::Space =>
+                Some(self.entries[*selection].1),
+            VirtualKeyCode::Escape => self.back,
+            key => [
+                VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3,
+                VirtualKeyCode::Key4, VirtualKeyCode::Key5, VirtualKeyCode::Key6,
+                VirtualKeyCode::Key7, VirtualKeyCode::Key8, VirtualKeyCode::Key9,
+            ]
+                .iter()
+                .position(|k| *k == key)
+                .and_then(|n| self.entries.get(n))
+                .map(|(_, action)| *action),
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut BTerm, selection: usize) {
+        ctx.set_active_console(2);
+        ctx.print_color_centered(2, self.title.1, RGB::named(BLACK), &self.title.0);
+        let mut y = 4;
+        for (text, color) in &self.lines {
+            ctx.print_color_centered(y, *color, RGB::named(BLACK), text);
+            y += 1;
+        }
+        y += 2;
+        for (n, (label, _)) in self.entries.iter().enumerate() {
+            let (fg, bg) = if n == selection {
+                (RGB::named(BLACK), RGB::named(YELLOW))
+            } else {
+                (RGB::named(WHITE), RGB::named(BLACK))
+            };
+            ctx.print_color_centered(y, fg, bg, format!(" {}. {} ", n + 1, label));
+            y += 1;
+        }
+    }
+}
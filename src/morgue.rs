@@ -0,0 +1,144 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+pub const MORGUE_FILE: &str = "morgue.txt";
+
+// A tally of what happened during the run, kept up to date by the systems
+// and summed up in the morgue file once the run ends.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RunStats {
+    pub turns: usize,
+    pub deepest_level: u32,
+    pub kills: BTreeMap<String, u32>,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub items_picked_up: Vec<String>,
+    pub items_used: Vec<String>,
+    // The turns the player spent on each level.
+    pub level_turns: BTreeMap<u32, usize>,
+    pub cause_of_death: Option<String>,
+    // Set once a wizard mode cheat is used, after which the run doesn't score.
+    pub wizard: bool,
+}
+
+fn glyph_to_char(glyph: FontCharType) -> char {
+    u8::try_from(glyph).map_or('?', to_char)
+}
+
+// The final map as text, showing the tiles the player has seen and the
+// entities they could see when the run ended.
+#[allow(clippy::cast_sign_loss)]
+fn ascii_map(ecs: &World, map: &Map, theme: &dyn MapTheme) -> Vec<String> {
+    let width = map.width as usize;
+    let mut rows: Vec<Vec<char>> = map.tiles
+        .chunks(width)
+        .zip(map.revealed_tiles.chunks(width))
+        .map(|(tiles, revealed)| tiles
+            .iter()
+            .zip(revealed)
+            .map(|(tile, revealed)| if *revealed {
+                glyph_to_char(theme.tile_to_render(*tile))
+            } else {
+                ' '
+            })
+            .collect()
+        )
+        .collect();
+
+    let visible = <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .map(|fov| fov.visible_tiles.clone())
+        .unwrap_or_default();
+    let mut place = |pos: Point, glyph: FontCharType| {
+        if let Some(idx) = map.try_idx(pos) {
+            rows[idx / width][idx % width] = glyph_to_char(glyph);
+        }
+    };
+    <(&Point, &Render)>::query()
+        .filter(!component::<Player>())
+        .iter(ecs)
+        .filter(|(pos, _)| visible.contains(pos))
+        .for_each(|(pos, render)| place(*pos, render.glyph));
+    <(&Point, &Render)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .for_each(|(pos, render)| place(*pos, render.glyph));
+
+    rows.into_iter().map(|row| row.into_iter().collect()).collect()
+}
+
+// Sum up a finished run: the same lines are written to the morgue file and
+// shown on the end screen.
+pub fn run_summary(ecs: &World, resources: &Resources) -> Vec<String> {
+    let stats = resources.get::<RunStats>().unwrap();
+    let seed = resources.get::<Seed>().unwrap();
+    let map = resources.get::<Map>().unwrap();
+    let theme = resources.get::<Box<dyn MapTheme>>().unwrap();
+    let victory = *resources.get::<TurnState>().unwrap() == TurnState::Victory;
+
+    let player = <Entity>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .copied()
+        .next()
+        .unwrap();
+    let inventory: Vec<String> = <(&Name, &Carried)>::query()
+        .iter(ecs)
+        .filter(|(_, carried)| carried.0 == player)
+        .map(|(name, _)| name.0.clone())
+        .collect();
+    let list = |names: &[String]| if names.is_empty() {
+        "nothing".to_string()
+    } else {
+        names.join(", ")
+    };
+
+    let mut lines = vec![
+        if victory {
+            "Recovered the Amulet of Yala.".to_string()
+        } else {
+            format!("Slain by {}.", stats.cause_of_death.as_deref().unwrap_or("something unknown"))
+        },
+        String::new(),
+    ];
+    if stats.wizard {
+        lines.push("Wizard mode was used, so this run doesn't score.".to_string());
+        lines.push(String::new());
+    }
+    lines.extend([
+        format!("Seed: {}", seed.0),
+        format!("Deepest level: {}", stats.deepest_level + 1),
+        format!("Turns: {}", stats.turns),
+        format!("Damage dealt: {}", stats.damage_dealt),
+        format!("Damage taken: {}", stats.damage_taken),
+    ]);
+    if stats.kills.is_empty() {
+        lines.push("Killed: nothing".to_string());
+    } else {
+        let kills: Vec<String> = stats.kills
+            .iter()
+            .map(|(name, count)| format!("{count} {name}"))
+            .collect();
+        lines.push(format!("Killed: {}", kills.join(", ")));
+    }
+    lines.push(format!("Items used: {}", list(&stats.items_used)));
+    lines.push(format!("Inventory: {}", list(&inventory)));
+    lines.push(String::new());
+    lines.extend(ascii_map(ecs, &map, theme.as_ref()));
+    lines
+}
+
+pub fn write_morgue(summary: &[String]) {
+    let mut contents = String::new();
+    for line in summary {
+        contents.push_str(line.trim_end());
+        contents.push('\n');
+    }
+    if let Err(e) = fs::write(MORGUE_FILE, contents) {
+        println!("Warning: unable to write {MORGUE_FILE}: {e}");
+    }
+}
@@ -0,0 +1,38 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+
+const OPTIONS_FILE: &str = "options.ron";
+
+// Player preferences, kept between runs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Options {
+    pub record_replays: bool,
+    #[serde(default)]
+    pub zoom: Zoom,
+    #[serde(default)]
+    pub camera_dead_zone: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self { record_replays: true, zoom: Zoom::Near, camera_dead_zone: false }
+    }
+}
+
+impl Options {
+    pub fn load() -> Self {
+        File::open(OPTIONS_FILE)
+            .ok()
+            .and_then(|file| ron::de::from_reader(file).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::new())
+            .expect("Unable to serialize options");
+        if let Err(e) = atomic_write(OPTIONS_FILE, &contents) {
+            println!("Warning: unable to write options to {OPTIONS_FILE}: {e}");
+        }
+    }
+}
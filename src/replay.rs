@@ -0,0 +1,217 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+
+pub const REPLAY_FILE: &str = "replay.ron";
+
+// Bump this whenever the layout of Replay changes.
+const REPLAY_VERSION: u32 = 3;
+
+// How many frames pass between recorded turns during normal playback, and
+// how many turns are played per frame when fast-forwarding.
+const PLAYBACK_FRAMES_PER_TURN: usize = 4;
+const FAST_FORWARD_TURNS_PER_FRAME: usize = 10;
+
+// Every key winit knows about. Keys are written to the replay file by name,
+// since VirtualKeyCode can't be serialized with the pinned serde version.
+#[allow(clippy::enum_glob_use)]
+const KEYS: &[VirtualKeyCode] = {
+    use VirtualKeyCode::*;
+    &[
+        Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+        Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+        F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24,
+        Snapshot, Scroll, Pause, Insert, Home, Delete, End, PageDown, PageUp,
+        Left, Up, Right, Down, Back, Return, Space, Compose, Caret, Numlock,
+        Numpad0, Numpad1, Numpad2, Numpad3, Numpad4,
+        Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+        NumpadAdd, NumpadDivide, NumpadDecimal, NumpadComma, NumpadEnter,
+        NumpadEquals, NumpadMultiply, NumpadSubtract,
+        AbntC1, AbntC2, Apostrophe, Apps, Asterisk, At, Ax, Backslash, Calculator,
+        Capital, Colon, Comma, Convert, Equals, Grave, Kana, Kanji, LAlt, LBracket,
+        LControl, LShift, LWin, Mail, MediaSelect, MediaStop, Minus, Mute,
+        MyComputer, NavigateForward, NavigateBackward, NextTrack, NoConvert, OEM102,
+        Period, PlayPause, Plus, Power, PrevTrack, RAlt, RBracket, RControl, RShift,
+        RWin, Semicolon, Slash, Sleep, Stop, Sysrq, Tab, Underline, Unlabeled,
+        VolumeDown, VolumeUp, Wake, WebBack, WebFavorites, WebForward, WebHome,
+        WebRefresh, WebSearch, WebStop, Yen, Copy, Paste, Cut,
+    ]
+};
+
+fn key_name(key: VirtualKeyCode) -> String {
+    format!("{key:?}")
+}
+
+fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    KEYS.iter().find(|key| key_name(**key) == name).copied()
+}
+
+// A run, reduced to its seed, the settings it was generated with and the
+// key pressed on every turn.
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    version: u32,
+    pub seed: Seed,
+    pub settings: GameSettings,
+    keys: Vec<String>,
+}
+
+impl Replay {
+    pub fn new(seed: Seed, settings: GameSettings) -> Self {
+        Self { version: REPLAY_VERSION, seed, settings, keys: Vec::new() }
+    }
+
+    // Replays come attached to bug reports, so one that is missing, broken
+    // or from another version is an error to report, not a crash.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let replay: Self = ron::de::from_reader(file).map_err(|e| e.to_string())?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!("replay version {} doesn't match version {REPLAY_VERSION}",
+                replay.version));
+        }
+        Ok(replay)
+    }
+
+    pub fn record(&mut self, key: VirtualKeyCode) {
+        self.keys.push(key_name(key));
+    }
+
+    pub fn save(&self, path: &str) {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::new())
+            .expect("Unable to serialize replay");
+        if let Err(e) = atomic_write(path, &contents) {
+            println!("Warning: unable to write replay to {path}: {e}");
+        }
+    }
+}
+
+// Feeds a recorded replay back in, one key per turn, with pause, single
+// stepping and fast-forward controls.
+pub struct Playback {
+    replay: Replay,
+    next_turn: usize,
+    frames_waited: usize,
+    paused: bool,
+    fast_forward: bool,
+    step_requested: bool,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next_turn: 0,
+            frames_waited: 0,
+            paused: false,
+            fast_forward: false,
+            step_requested: false,
+        }
+    }
+
+    pub fn seed(&self) -> Seed {
+        self.replay.seed
+    }
+
+    pub fn settings(&self) -> GameSettings {
+        self.replay.settings.clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_turn >= self.replay.keys.len()
+    }
+
+    // Handle the playback controls pressed this frame.
+    pub fn control(&mut self, key: Option<VirtualKeyCode>) {
+        match key {
+            Some(VirtualKeyCode::Space) => self.paused = !self.paused,
+            Some(VirtualKeyCode::Period) => {
+                self.paused = true;
+                self.step_requested = true;
+            }
+            Some(VirtualKeyCode::F) => self.fast_forward = !self.fast_forward,
+            _ => {}
+        }
+    }
+
+    // How many recorded turns should be played this frame.
+    pub fn turns_this_frame(&mut self) -> usize {
+        if self.paused {
+            let turns = usize::from(self.step_requested);
+            self.step_requested = false;
+            turns
+        } else if self.fast_forward {
+            FAST_FORWARD_TURNS_PER_FRAME
+        } else {
+            self.frames_waited += 1;
+            if self.frames_waited >= PLAYBACK_FRAMES_PER_TURN {
+                self.frames_waited = 0;
+                1
+            } else {
+                0
+            }
+        }
+    }
+
+    pub fn next_key(&mut self) -> Option<VirtualKeyCode> {
+        let key = self.replay.keys.get(self.next_turn).and_then(|name| {
+            let key = key_from_name(name);
+            if key.is_none() {
+                println!("Warning: unknown key [{name}] in replay");
+            }
+            key
+        });
+        self.next_turn += 1;
+        key
+    }
+
+    pub fn status(&self) -> String {
+        format!("Replay: turn {} / {}{}{}",
+            self.next_turn.min(self.replay.keys.len()),
+            self.replay.keys.len(),
+            if self.paused { " [paused]" } else { "" },
+            if self.fast_forward { " [>>]" } else { "" },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir().join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn a_saved_replay_loads_back() {
+        let path = temp_path("rusty-crawler-test-replay.ron");
+        let mut replay = Replay::new(Seed(42), GameSettings::default());
+        replay.record(VirtualKeyCode::Left);
+        replay.record(VirtualKeyCode::Period);
+        replay.save(&path);
+
+        let mut playback = Playback::new(Replay::load(&path).unwrap());
+        assert_eq!(playback.seed(), Seed(42));
+        assert_eq!(playback.next_key(), Some(VirtualKeyCode::Left));
+        assert_eq!(playback.next_key(), Some(VirtualKeyCode::Period));
+        assert!(playback.is_finished());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn bad_replays_are_errors() {
+        assert!(Replay::load(&temp_path("rusty-crawler-no-such-replay.ron")).is_err());
+
+        let path = temp_path("rusty-crawler-bad-replays.ron");
+        std::fs::write(&path, "not a replay").unwrap();
+        assert!(Replay::load(&path).is_err());
+
+        let mut old = Replay::new(Seed(1), GameSettings::default());
+        old.version = REPLAY_VERSION - 1;
+        old.save(&path);
+        assert!(Replay::load(&path).err().is_some_and(|e| e.contains("version")));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,538 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::path::Path;
+
+const SAVE_FILE: &str = "savegame.ron";
+
+// Bump this whenever the layout of SaveGame changes, so that an old file is
+// rejected instead of being loaded into the wrong fields.
+const SAVE_VERSION: u32 = 17;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Format(ron::Error),
+    Version(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{e}"),
+            SaveError::Format(e) => write!(f, "{e}"),
+            SaveError::Version(v) => write!(f,
+                "save file version {v} doesn't match version {SAVE_VERSION}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<ron::Error> for SaveError {
+    fn from(e: ron::Error) -> Self {
+        SaveError::Format(e)
+    }
+}
+
+// bracket-lib's geometry and color types can't be serialized with the serde
+// version this crate is pinned to, so they are mirrored here.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct SavedPoint {
+    x: i32,
+    y: i32,
+}
+
+impl From<Point> for SavedPoint {
+    fn from(pt: Point) -> Self {
+        Self { x: pt.x, y: pt.y }
+    }
+}
+
+impl From<SavedPoint> for Point {
+    fn from(pt: SavedPoint) -> Self {
+        Point::new(pt.x, pt.y)
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct SavedColor(f32, f32, f32, f32);
+
+impl From<RGBA> for SavedColor {
+    fn from(c: RGBA) -> Self {
+        Self(c.r, c.g, c.b, c.a)
+    }
+}
+
+impl From<SavedColor> for RGBA {
+    fn from(c: SavedColor) -> Self {
+        RGBA::from_f32(c.0, c.1, c.2, c.3)
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct SavedRender {
+    fg: SavedColor,
+    bg: SavedColor,
+    glyph: FontCharType,
+}
+
+// Components that refer to other entities store the index of that entity in
+// the list of saved entities, which is turned back into a live Entity on load.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct SavedWantsToMove {
+    entity: usize,
+    destination: SavedPoint,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct SavedWantsToAttack {
+    attacker: usize,
+    victim: usize,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct SavedActivateItem {
+    used_by: usize,
+    item: usize,
+    target: Option<SavedPoint>,
+}
+
+// Every component of one entity. These are also used to store the
+// entities of levels the player isn't on.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SavedEntity {
+    position: Option<SavedPoint>,
+    render: Option<SavedRender>,
+    player: Option<Player>,
+    enemy: Option<Enemy>,
+    moving_randomly: Option<MovingRandomly>,
+    wants_to_move: Option<SavedWantsToMove>,
+    health: Option<Health>,
+    name: Option<Name>,
+    description: Option<Description>,
+    wants_to_attack: Option<SavedWantsToAttack>,
+    chasing_player: Option<ChasingPlayer>,
+    item: Option<Item>,
+    amulet_of_yala: Option<AmuletOfYala>,
+    // Visible tiles are recalculated on load, only the radius is kept.
+    field_of_view: Option<i32>,
+    provides_healing: Option<ProvidesHealing>,
+    provides_dungeon_map: Option<ProvidesDungeonMap>,
+    carried: Option<usize>,
+    activate_item: Option<SavedActivateItem>,
+    damage: Option<Damage>,
+    weapon: Option<Weapon>,
+    speed: Option<Speed>,
+    energy: Option<Energy>,
+    speed_effect: Option<SpeedEffect>,
+    provides_haste: Option<ProvidesHaste>,
+    provides_slow: Option<ProvidesSlow>,
+    provides_damage: Option<ProvidesDamage>,
+    ranged: Option<Ranged>,
+    area_of_effect: Option<AreaOfEffect>,
+    regeneration: Option<Regeneration>,
+    resting: Option<Resting>,
+    hunting: Option<Hunting>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveGame {
+    version: u32,
+    seed: Seed,
+    settings: GameSettings,
+    // The data the run was started with, whatever the files hold by now.
+    campaign: Campaign,
+    templates: Templates,
+    rng: RandomNumberGenerator,
+    turn_state: TurnState,
+    map: Map,
+    camera: Camera,
+    theme: ThemeKind,
+    dungeon: Dungeon,
+    stats: RunStats,
+    log: GameLog,
+    wizard: Wizard,
+    achievement_progress: AchievementProgress,
+    entities: Vec<SavedEntity>,
+}
+
+pub fn has_saved_game() -> bool {
+    Path::new(SAVE_FILE).exists()
+}
+
+pub fn delete_saved_game() {
+    if has_saved_game() {
+        if let Err(e) = fs::remove_file(SAVE_FILE) {
+            println!("Warning: unable to delete {SAVE_FILE}: {e}");
+        }
+    }
+}
+
+// The random number generator is saved as it stands, so a resumed game
+// rolls just as the run would have gone on to.
+pub fn save_game(ecs: &World, resources: &Resources) -> Result<(), SaveError> {
+    let contents = ron::ser::to_string_pretty(&snapshot_game(ecs, resources),
+        ron::ser::PrettyConfig::new())?;
+    atomic_write(SAVE_FILE, &contents)?;
+    Ok(())
+}
+
+fn snapshot_game(ecs: &World, resources: &Resources) -> SaveGame {
+    // In the order the world visits them, which restore_entities rebuilds,
+    // so the systems go on meeting them in that order once resumed.
+    let entities: Vec<Entity> = <Entity>::query().iter(ecs).copied().collect();
+    SaveGame {
+        version: SAVE_VERSION,
+        seed: *resources.get::<Seed>().unwrap(),
+        settings: resources.get::<GameSettings>().unwrap().clone(),
+        campaign: resources.get::<Campaign>().unwrap().clone(),
+        templates: resources.get::<Templates>().unwrap().clone(),
+        rng: resources.get::<RandomNumberGenerator>().unwrap().clone(),
+        turn_state: *resources.get::<TurnState>().unwrap(),
+        map: resources.get::<Map>().unwrap().clone(),
+        camera: *resources.get::<Camera>().unwrap(),
+        theme: resources.get::<Box<dyn MapTheme>>().unwrap().kind(),
+        dungeon: resources.get::<Dungeon>().unwrap().clone(),
+        stats: resources.get::<RunStats>().unwrap().clone(),
+        log: resources.get::<GameLog>().unwrap().clone(),
+        wizard: *resources.get::<Wizard>().unwrap(),
+        achievement_progress: resources.get::<AchievementProgress>().unwrap().clone(),
+        entities: snapshot_entities(ecs, &entities),
+    }
+}
+
+// Write a file by way of a temporary one renamed over it, so a crash
+// mid-write leaves the previous file whole instead of a truncated one.
+pub fn atomic_write(path: &str, contents: &str) -> std::io::Result<()> {
+    let tmp = format!("{path}.tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+// Capture the components of the given entities. References to entities
+// outside of the list are dropped.
+pub fn snapshot_entities(ecs: &World, entities: &[Entity]) -> Vec<SavedEntity> {
+    let indices: HashMap<Entity, usize> = entities
+        .iter()
+        .enumerate()
+        .map(|(idx, entity)| (*entity, idx))
+        .collect();
+    entities
+        .iter()
+        .map(|entity| save_entity(&ecs.entry_ref(*entity).unwrap(), &indices))
+        .collect()
+}
+
+// Recreate entities captured by snapshot_entities, returning them in the
+// same order. They are filled in one by one in that order, so the world
+// visits them as it did those they were captured from.
+pub fn restore_entities(ecs: &mut World, saved: Vec<SavedEntity>) -> Vec<Entity> {
+    // Create every entity up front, so that references between them can be
+    // resolved while their components are added.
+    let entities: Vec<Entity> = saved.iter().map(|_| ecs.push(())).collect();
+    for (saved, entity) in saved.into_iter().zip(entities.iter()) {
+        load_entity(saved, *entity, &entities, ecs);
+    }
+    entities
+}
+
+fn save_entity(entry: &world::EntryRef, indices: &HashMap<Entity, usize>) -> SavedEntity {
+    let index_of = |entity: &Entity| indices.get(entity).copied();
+    SavedEntity {
+        position: entry.get_component::<Point>().ok().map(|pt| (*pt).into()),
+        render: entry.get_component::<Render>().ok().map(|render| SavedRender {
+            fg: render.color.fg.into(),
+            bg: render.color.bg.into(),
+            glyph: render.glyph,
+        }),
+        player: entry.get_component::<Player>().ok().copied(),
+        enemy: entry.get_component::<Enemy>().ok().copied(),
+        moving_randomly: entry.get_component::<MovingRandomly>().ok().copied(),
+        wants_to_move: entry.get_component::<WantsToMove>().ok().and_then(|m|
+            Some(SavedWantsToMove {
+                entity: index_of(&m.entity)?,
+                destination: m.destination.into(),
+            })
+        ),
+        health: entry.get_component::<Health>().ok().copied(),
+        name: entry.get_component::<Name>().ok().cloned(),
+        description: entry.get_component::<Description>().ok().cloned(),
+        wants_to_attack: entry.get_component::<WantsToAttack>().ok().and_then(|a|
+            Some(SavedWantsToAttack {
+                attacker: index_of(&a.attacker)?,
+                victim: index_of(&a.victim)?,
+            })
+        ),
+        chasing_player: entry.get_component::<ChasingPlayer>().ok().copied(),
+        item: entry.get_component::<Item>().ok().copied(),
+        amulet_of_yala: entry.get_component::<AmuletOfYala>().ok().copied(),
+        field_of_view: entry.get_component::<FieldOfView>().ok().map(|fov| fov.radius),
+        provides_healing: entry.get_component::<ProvidesHealing>().ok().copied(),
+        provides_dungeon_map: entry.get_component::<ProvidesDungeonMap>().ok().copied(),
+        carried: entry.get_component::<Carried>().ok().and_then(|c| index_of(&c.0)),
+        activate_item: entry.get_component::<ActivateItem>().ok().and_then(|a|
+            Some(SavedActivateItem {
+                used_by: index_of(&a.used_by)?,
+                item: index_of(&a.item)?,
+                target: a.target.map(SavedPoint::from),
+            })
+        ),
+        damage: entry.get_component::<Damage>().ok().copied(),
+        weapon: entry.get_component::<Weapon>().ok().copied(),
+        speed: entry.get_component::<Speed>().ok().copied(),
+        energy: entry.get_component::<Energy>().ok().copied(),
+        speed_effect: entry.get_component::<SpeedEffect>().ok().copied(),
+        provides_haste: entry.get_component::<ProvidesHaste>().ok().copied(),
+        provides_slow: entry.get_component::<ProvidesSlow>().ok().copied(),
+        provides_damage: entry.get_component::<ProvidesDamage>().ok().copied(),
+        ranged: entry.get_component::<Ranged>().ok().copied(),
+        area_of_effect: entry.get_component::<AreaOfEffect>().ok().copied(),
+        regeneration: entry.get_component::<Regeneration>().ok().copied(),
+        resting: entry.get_component::<Resting>().ok().copied(),
+        hunting: entry.get_component::<Hunting>().ok().copied(),
+    }
+}
+
+// Rebuilds the world and resources from the save file. The file is removed
+// once it has been read, so a run can only be continued once.
+pub fn load_game() -> Result<(World, Resources), SaveError> {
+    let save: SaveGame = ron::de::from_reader(File::open(SAVE_FILE)?)?;
+    if save.version != SAVE_VERSION {
+        return Err(SaveError::Version(save.version));
+    }
+    let game = restore_game(save);
+    delete_saved_game();
+    Ok(game)
+}
+
+fn restore_game(save: SaveGame) -> (World, Resources) {
+    let mut ecs = World::default();
+    restore_entities(&mut ecs, save.entities);
+
+    let mut resources = Resources::default();
+    resources.insert(save.map);
+    resources.insert(save.camera);
+    resources.insert(save.turn_state);
+    resources.insert(save.theme.build());
+    resources.insert(save.rng);
+    resources.insert(save.seed);
+    resources.insert(save.settings);
+    resources.insert(save.campaign);
+    resources.insert(save.templates);
+    resources.insert(save.dungeon);
+    resources.insert(save.stats);
+    resources.insert(save.log);
+    resources.insert(save.wizard);
+    resources.insert(save.achievement_progress);
+    resources.insert(Achievements::load());
+    resources.insert(Events::default());
+    resources.insert(Travel::default());
+    resources.insert(Look::new(Point::zero()));
+    resources.insert(None::<Targeting>);
+    (ecs, resources)
+}
+
+fn load_entity(saved: SavedEntity, entity: Entity, entities: &[Entity], ecs: &mut World) {
+    let mut entry = ecs.entry(entity).unwrap();
+    load_item_effects(&saved, &mut entry);
+    if let Some(pt) = saved.position {
+        entry.add_component(Point::from(pt));
+    }
+    if let Some(render) = saved.render {
+        entry.add_component(Render {
+            color: ColorPair::new(RGBA::from(render.fg), RGBA::from(render.bg)),
+            glyph: render.glyph,
+        });
+    }
+    if let Some(player) = saved.player {
+        entry.add_component(player);
+    }
+    if let Some(enemy) = saved.enemy {
+        entry.add_component(enemy);
+    }
+    if let Some(moving_randomly) = saved.moving_randomly {
+        entry.add_component(moving_randomly);
+    }
+    if let Some(m) = saved.wants_to_move {
+        entry.add_component(WantsToMove {
+            entity: entities[m.entity],
+            destination: m.destination.into(),
+        });
+    }
+    if let Some(health) = saved.health {
+        entry.add_component(health);
+    }
+    if let Some(name) = saved.name {
+        entry.add_component(name);
+    }
+    if let Some(description) = saved.description {
+        entry.add_component(description);
+    }
+    if let Some(a) = saved.wants_to_attack {
+        entry.add_component(WantsToAttack {
+            attacker: entities[a.attacker],
+            victim: entities[a.victim],
+        });
+    }
+    if let Some(chasing_player) = saved.chasing_player {
+        entry.add_component(chasing_player);
+    }
+    if let Some(item) = saved.item {
+        entry.add_component(item);
+    }
+    if let Some(amulet) = saved.amulet_of_yala {
+        entry.add_component(amulet);
+    }
+    if let Some(radius) = saved.field_of_view {
+        entry.add_component(FieldOfView::new(radius));
+    }
+    if let Some(owner) = saved.carried {
+        entry.add_component(Carried(entities[owner]));
+    }
+    if let Some(a) = saved.activate_item {
+        entry.add_component(ActivateItem {
+            used_by: entities[a.used_by],
+            item: entities[a.item],
+            target: a.target.map(Point::from),
+        });
+    }
+    if let Some(damage) = saved.damage {
+        entry.add_component(damage);
+    }
+    if let Some(weapon) = saved.weapon {
+        entry.add_component(weapon);
+    }
+    if let Some(speed) = saved.speed {
+        entry.add_component(speed);
+    }
+    if let Some(energy) = saved.energy {
+        entry.add_component(energy);
+    }
+    if let Some(effect) = saved.speed_effect {
+        entry.add_component(effect);
+    }
+    if let Some(regeneration) = saved.regeneration {
+        entry.add_component(regeneration);
+    }
+    if let Some(resting) = saved.resting {
+        entry.add_component(resting);
+    }
+    if let Some(hunting) = saved.hunting {
+        entry.add_component(hunting);
+    }
+}
+
+// What an item does when it's used, and how it's aimed.
+fn load_item_effects(saved: &SavedEntity, entry: &mut legion::world::Entry) {
+    if let Some(healing) = saved.provides_healing {
+        entry.add_component(healing);
+    }
+    if let Some(mapper) = saved.provides_dungeon_map {
+        entry.add_component(mapper);
+    }
+    if let Some(haste) = saved.provides_haste {
+        entry.add_component(haste);
+    }
+    if let Some(slow) = saved.provides_slow {
+        entry.add_component(slow);
+    }
+    if let Some(damage) = saved.provides_damage {
+        entry.add_component(damage);
+    }
+    if let Some(ranged) = saved.ranged {
+        entry.add_component(ranged);
+    }
+    if let Some(area) = saved.area_of_effect {
+        entry.add_component(area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+
+    const KEYS: [VirtualKeyCode; 5] = [VirtualKeyCode::Left, VirtualKeyCode::Up,
+        VirtualKeyCode::Period, VirtualKeyCode::Right, VirtualKeyCode::Down];
+
+    fn play(state: &mut State, turns: std::ops::Range<usize>) {
+        for turn in turns {
+            state.play_turn(Some(KEYS[turn % KEYS.len()]));
+        }
+    }
+
+    // Everything that tells one run from another: where everything stands
+    // and how it fares, what was logged and the next roll of the dice.
+    fn fingerprint(state: &State) -> (Vec<String>, String, u64) {
+        let mut entities: Vec<String> = <(Option<&Name>, Option<&Point>, Option<&Health>)>::query()
+            .iter(&state.ecs)
+            .map(|(name, pos, health)| format!("{:?} {:?} {:?}",
+                name.map(|name| &name.0), pos, health.map(|health| health.current)))
+            .collect();
+        entities.sort();
+        let log = format!("{:?}", state.resources.get::<GameLog>().unwrap().latest(1000));
+        let roll = state.resources.get::<RandomNumberGenerator>().unwrap().clone().next_u64();
+        (entities, log, roll)
+    }
+
+    #[test]
+    fn a_resumed_game_plays_on_as_if_never_saved() {
+        for seed in 1..=4 {
+            let mut straight = State::new(Seed(seed), GameSettings::default());
+            straight.keep_records = false;
+            let mut resumed = State::new(Seed(seed), GameSettings::default());
+            resumed.keep_records = false;
+            play(&mut straight, 0..40);
+            play(&mut resumed, 0..20);
+
+            // Through RON and back, as the save file would be.
+            let text = ron::ser::to_string(&snapshot_game(&resumed.ecs, &resumed.resources)).unwrap();
+            let (ecs, resources) = restore_game(ron::de::from_str(&text).unwrap());
+            resumed.resume(ecs, resources);
+            play(&mut resumed, 20..40);
+
+            assert!(fingerprint(&straight) == fingerprint(&resumed), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn entities_and_references_survive_a_round_trip() {
+        let mut ecs = World::default();
+        let player = ecs.push((Player { map_level: 2 }, Point::new(3, 4), Health { current: 7, max: 10 }));
+        let potion = ecs.push((Item, Name("Healing Potion".to_string()),
+            ProvidesHealing { amount: 6 }, Carried(player)));
+        ecs.push(((), ActivateItem { used_by: player, item: potion, target: Some(Point::new(5, 6)) }));
+        let entities: Vec<Entity> = <Entity>::query().iter(&ecs).copied().collect();
+
+        // Through RON and back, as the save file would be.
+        let saved = snapshot_entities(&ecs, &entities);
+        let text = ron::ser::to_string(&saved).unwrap();
+        let saved: Vec<SavedEntity> = ron::de::from_str(&text).unwrap();
+        let mut loaded = World::default();
+        let restored = restore_entities(&mut loaded, saved);
+        let index = |entity: Entity| entities.iter().position(|e| *e == entity).unwrap();
+        let (player, potion) = (restored[index(player)], restored[index(potion)]);
+
+        let player_entry = loaded.entry_ref(player).unwrap();
+        assert_eq!(player_entry.get_component::<Player>().unwrap().map_level, 2);
+        assert_eq!(*player_entry.get_component::<Point>().unwrap(), Point::new(3, 4));
+        assert_eq!(player_entry.get_component::<Health>().unwrap().current, 7);
+
+        let potion_entry = loaded.entry_ref(potion).unwrap();
+        assert_eq!(potion_entry.get_component::<Name>().unwrap().0, "Healing Potion");
+        assert_eq!(potion_entry.get_component::<ProvidesHealing>().unwrap().amount, 6);
+        assert!(potion_entry.get_component::<Carried>().unwrap().0 == player);
+        assert!(potion_entry.get_component::<Point>().is_err());
+
+        let activate = *<&ActivateItem>::query().iter(&loaded).next().unwrap();
+        assert!(activate.used_by == player && activate.item == potion);
+        assert_eq!(activate.target, Some(Point::new(5, 6)));
+    }
+}
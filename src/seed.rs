@@ -0,0 +1,47 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// The seed that drives every random decision in a run: map generation,
+// spawning and monster AI. It is kept as a resource so the HUD can show it,
+// letting players share a dungeon and bug reports be reproduced.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Seed(pub u64);
+
+impl Seed {
+    pub fn random() -> Self {
+        Self(RandomNumberGenerator::new().next_u64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+
+    // Where everything stands after a few turns spent waiting.
+    fn play(seed: u64) -> (Vec<TileType>, Vec<(i32, i32)>, u64) {
+        let mut state = State::new(Seed(seed), GameSettings::default());
+        state.keep_records = false;
+        for _ in 0..20 {
+            state.play_turn(Some(VirtualKeyCode::Period));
+        }
+        let tiles = state.resources.get::<Map>().unwrap().tiles.clone();
+        let mut positions: Vec<(i32, i32)> = <&Point>::query()
+            .iter(&state.ecs)
+            .map(|pt| (pt.x, pt.y))
+            .collect();
+        positions.sort_unstable();
+        let next_roll = state.resources.get_mut::<RandomNumberGenerator>().unwrap().next_u64();
+        (tiles, positions, next_roll)
+    }
+
+    #[test]
+    fn the_same_seed_plays_the_same_game() {
+        assert!(play(1234) == play(1234));
+    }
+
+    #[test]
+    fn different_seeds_build_different_dungeons() {
+        assert!(play(1234).0 != play(4321).0);
+    }
+}
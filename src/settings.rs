@@ -0,0 +1,43 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const TEMPLATES_FILE: &str = "resources/template.ron";
+
+// Overrides for how a run's levels are generated and played, mostly for
+// debugging a particular generator or depth. They are kept with the game, so
+// saved games and replays generate and play the same levels again.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameSettings {
+    pub architect: Option<ArchitectKind>,
+    pub theme: Option<ThemeKind>,
+    pub start_level: u32,
+    pub templates: Option<String>,
+    // Forbid diagonal steps past the corner of a wall.
+    pub no_corner_cutting: bool,
+}
+
+impl GameSettings {
+    pub fn templates_path(&self) -> &str {
+        self.templates.as_deref().unwrap_or(TEMPLATES_FILE)
+    }
+
+    pub fn step_rule(&self) -> StepRule {
+        if self.no_corner_cutting {
+            StepRule::EightWayNoCornerCutting
+        } else {
+            StepRule::EightWay
+        }
+    }
+
+    // The campaign's description of a level, with the overrides applied.
+    pub fn apply(&self, level: &CampaignLevel) -> CampaignLevel {
+        let mut level = level.clone();
+        if self.architect.is_some() {
+            level.architect = self.architect;
+        }
+        if self.theme.is_some() {
+            level.theme = self.theme;
+        }
+        level
+    }
+}
@@ -0,0 +1,230 @@
+use crate::headless::HeadlessRunner;
+use crate::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// How one simulated run went.
+struct RunResult {
+    victory: bool,
+    timed_out: bool,
+    death_level: u32,
+    stats: RunStats,
+}
+
+fn simulate_run(
+    seed: Seed,
+    settings: GameSettings,
+    agent_name: &str,
+    max_turns: usize
+) -> RunResult {
+    let mut agent = agent_by_name(agent_name).expect("Unknown agent");
+    let mut runner = HeadlessRunner::new(seed, settings);
+    runner.keep_records(false);
+    while !runner.is_finished() && runner.turns() < max_turns {
+        let action = agent.act(&runner.view());
+        runner.act(action);
+    }
+    RunResult {
+        victory: runner.turn_state() == TurnState::Victory,
+        timed_out: !runner.is_finished(),
+        death_level: runner.map_level(),
+        stats: runner.stats(),
+    }
+}
+
+// The numbers behind the templates: how often runs are won, where and to
+// what the player dies, how long each level takes and what gets used.
+#[derive(Default)]
+struct Report {
+    first_seed: u64,
+    runs: usize,
+    wins: usize,
+    timed_out: usize,
+    deaths_on_level: BTreeMap<u32, usize>,
+    killed_by: BTreeMap<String, usize>,
+    level_turns: BTreeMap<u32, (usize, usize)>,
+    items_picked_up: BTreeMap<String, usize>,
+    items_used: BTreeMap<String, usize>,
+}
+
+impl Report {
+    fn add(&mut self, result: &RunResult) {
+        self.runs += 1;
+        if result.victory {
+            self.wins += 1;
+        } else if result.timed_out {
+            self.timed_out += 1;
+        } else {
+            *self.deaths_on_level.entry(result.death_level + 1).or_insert(0) += 1;
+            let killer = result.stats.cause_of_death.as_deref().unwrap_or("something");
+            *self.killed_by.entry(killer.to_string()).or_insert(0) += 1;
+        }
+        for (level, turns) in &result.stats.level_turns {
+            let (total, runs) = self.level_turns.entry(level + 1).or_insert((0, 0));
+            *total += turns;
+            *runs += 1;
+        }
+        for item in &result.stats.items_picked_up {
+            *self.items_picked_up.entry(item.clone()).or_insert(0) += 1;
+        }
+        for item in &result.stats.items_used {
+            *self.items_used.entry(item.clone()).or_insert(0) += 1;
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn win_rate(&self) -> f64 {
+        if self.runs == 0 { 0.0 } else { self.wins as f64 / self.runs as f64 }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn average_level_turns(&self) -> BTreeMap<u32, f64> {
+        self.level_turns
+            .iter()
+            .map(|(level, (total, runs))| (*level, *total as f64 / *runs as f64))
+            .collect()
+    }
+
+    // One value per row, so every figure can be filtered on in a
+    // spreadsheet.
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("metric,key,value\n");
+        let mut row = |metric: &str, key: &str, value: String| {
+            writeln!(csv, "{metric},{},{value}", csv_field(key)).unwrap();
+        };
+        row("first_seed", "", self.first_seed.to_string());
+        row("runs", "", self.runs.to_string());
+        row("wins", "", self.wins.to_string());
+        row("timed_out", "", self.timed_out.to_string());
+        row("win_rate", "", format!("{:.3}", self.win_rate()));
+        for (level, count) in &self.deaths_on_level {
+            row("deaths_on_level", &level.to_string(), count.to_string());
+        }
+        for (name, count) in &self.killed_by {
+            row("killed_by", name, count.to_string());
+        }
+        for (level, turns) in self.average_level_turns() {
+            row("average_turns_on_level", &level.to_string(), format!("{turns:.1}"));
+        }
+        for (name, count) in &self.items_picked_up {
+            row("items_picked_up", name, count.to_string());
+        }
+        for (name, count) in &self.items_used {
+            row("items_used", name, count.to_string());
+        }
+        csv
+    }
+
+    fn to_json(&self) -> String {
+        let object = |entries: Vec<(String, String)>| {
+            let fields: Vec<String> = entries
+                .iter()
+                .map(|(key, value)| format!("{}: {value}", json_string(key)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        };
+        let counts = |map: &BTreeMap<String, usize>| object(map
+            .iter()
+            .map(|(key, count)| (key.clone(), count.to_string()))
+            .collect());
+        let mut json = String::from("{\n");
+        let mut field = |name: &str, value: String| {
+            if json.len() > 2 {
+                json.push_str(",\n");
+            }
+            write!(json, "  {}: {value}", json_string(name)).unwrap();
+        };
+        field("first_seed", self.first_seed.to_string());
+        field("runs", self.runs.to_string());
+        field("wins", self.wins.to_string());
+        field("timed_out", self.timed_out.to_string());
+        field("win_rate", format!("{:.3}", self.win_rate()));
+        field("deaths_on_level", object(self.deaths_on_level
+            .iter()
+            .map(|(level, count)| (level.to_string(), count.to_string()))
+            .collect()));
+        field("killed_by", counts(&self.killed_by));
+        field("average_turns_on_level", object(self.average_level_turns()
+            .iter()
+            .map(|(level, turns)| (level.to_string(), format!("{turns:.1}")))
+            .collect()));
+        field("items_picked_up", counts(&self.items_picked_up));
+        field("items_used", counts(&self.items_used));
+        json.push_str("\n}\n");
+        json
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+// Play `runs` games with the named agent, on consecutive seeds starting at
+// first_seed, spread over every core. The report is written to the given
+// path, as JSON if it ends in .json and as CSV otherwise, or printed.
+pub fn run_simulations(
+    first_seed: Seed,
+    runs: usize,
+    settings: &GameSettings,
+    agent_name: &str,
+    max_turns: usize,
+    report_path: Option<&str>
+) {
+    let threads = thread::available_parallelism().map_or(1, usize::from).min(runs.max(1));
+    let next_run = AtomicUsize::new(0);
+    let report = Mutex::new(Report { first_seed: first_seed.0, ..Report::default() });
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let run = next_run.fetch_add(1, Ordering::Relaxed);
+                if run >= runs {
+                    break;
+                }
+                let seed = Seed(first_seed.0.wrapping_add(run as u64));
+                let result = simulate_run(seed, settings.clone(), agent_name, max_turns);
+                report.lock().unwrap().add(&result);
+            });
+        }
+    });
+
+    // Runs finish in any order, but every figure is a sum, so the report
+    // is the same whatever the order.
+    let report = report.into_inner().unwrap();
+    let contents = match report_path {
+        Some(path) if Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json")) => report.to_json(),
+        _ => report.to_csv(),
+    };
+    if let Some(path) = report_path {
+        println!("Won {} of {} runs.", report.wins, report.runs);
+        if let Err(e) = fs::write(path, contents) {
+            println!("Warning: unable to write {path}: {e}");
+        }
+    } else {
+        print!("{contents}");
+    }
+}
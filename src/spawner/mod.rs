@@ -1,9 +1,9 @@
 use crate::prelude::*;
-use template::Templates;
+pub use template::Templates;
 mod template;
 
 pub fn spawn_player(ecs: &mut World, pos: Point) {
-    ecs.push(
+    let player = ecs.push(
         (
             Player{
                 map_level: 0,
@@ -16,44 +16,16 @@ pub fn spawn_player(ecs: &mut World, pos: Point) {
             Health{current: 10, max: 10},
             FieldOfView::new(8),
             Damage(1),
+            Speed(NORMAL_SPEED),
+            Energy(0),
         )
     );
-}
-
-pub fn spawn_monster(
-    ecs: &mut World,
-    rng: &mut RandomNumberGenerator,
-    pos: Point
-) {
-
-    // You can destructure data out of tuples using this syntax. 
-    // let (a, b, c) = function_returns_three_values. This lets you name your variables
-    let (hp, name, glyph) = match rng.roll_dice(1, 10) {
-        1..=8 => goblin(),
-        _ => orc(),
-    };
-    ecs.push(
-        (
-            Enemy,
-            pos,
-            Render{
-                color: ColorPair::new(WHITE, BLACK),
-                glyph,
-            },
-            ChasingPlayer{},
-            Health{current: hp, max: hp},
-            Name(name),
-            FieldOfView::new(6),
-        )
-    );
-}
-
-fn goblin() -> (i32, String, FontCharType) {
-    (1, "Goblin".to_string(), to_cp437('g'))
-}
-
-fn orc() -> (i32, String, FontCharType) {
-    (2, "Orc".to_string(), to_cp437('o'))
+    // Legion builds entities from tuples of at most eight components.
+    if let Some(mut entry) = ecs.entry(player) {
+        entry.add_component(Regeneration::new(8));
+        entry.add_component(Description(
+            "An adventurer, come in search of the Amulet of Yala.".to_string()));
+    }
 }
 
 pub fn spawn_amulet_of_yala(ecs: &mut World, pos: Point) {
@@ -66,60 +38,29 @@ pub fn spawn_amulet_of_yala(ecs: &mut World, pos: Point) {
                 color: ColorPair::new(WHITE, BLACK),
                 glyph: to_cp437('/')
             },
-            Name("Amulet of Yala".to_string())
-        )
-    );
-}
-
-pub fn spawn_healing_potion(ecs: &mut World, pos: Point) {
-    ecs.push(
-        (
-            Item,
-            pos,
-            Render{
-                color: ColorPair::new(WHITE, BLACK),
-                glyph: to_cp437('!')
-            },
-            Name("Healing Potion".to_string()),
-            ProvidesHealing{amount: 6}
-        )
-    );
-}
-
-pub fn spawn_magic_mapper(ecs: &mut World, pos: Point) {
-    ecs.push(
-        (
-            Item,
-            pos,
-            Render{
-                color: ColorPair::new(WHITE, BLACK),
-                glyph: to_cp437('{')
-            },
-            Name("Dungeon Map".to_string()),
-            ProvidesDungeonMap{}
+            Name("Amulet of Yala".to_string()),
+            Description("The amulet you came for. It hums faintly in the dark.".to_string())
         )
     );
 }
 
-pub fn spawn_entity(
+pub fn spawn_level(
     ecs: &mut World,
     rng: &mut RandomNumberGenerator,
-    pos: Point
+    templates: &Templates,
+    template_levels: &[usize],
+    spawn_points: &[Point],
+    log: &mut GameLog
 ) {
-    let roll = rng.roll_dice(1, 6);
-    match roll {
-        1 => spawn_healing_potion(ecs, pos),
-        2 => spawn_magic_mapper(ecs, pos),
-        _ => spawn_monster(ecs, rng, pos),
-    }
+    templates.spawn_entities(ecs, rng, template_levels, spawn_points, log);
 }
 
-pub fn spawn_level(
+pub fn spawn_boss(
     ecs: &mut World,
-    rng: &mut RandomNumberGenerator,
-    level: usize,
-    spawn_points: &[Point]
+    templates: &Templates,
+    name: &str,
+    pos: Point,
+    log: &mut GameLog
 ) {
-    let template = Templates::load();
-    template.spawn_entities(ecs, rng, level, spawn_points);
+    templates.spawn_named(ecs, name, pos, log);
 }
\ No newline at end of file
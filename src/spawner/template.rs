@@ -1,11 +1,11 @@
 use crate::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use ron::de::from_reader;
 use std::fs::File;
 use std::collections::HashSet;
 use legion::systems::CommandBuffer;
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Template {
     pub entity_type : EntityType,
     pub levels : HashSet<usize>,
@@ -14,22 +14,32 @@ pub struct Template {
     pub glyph : char,
     pub provides : Option<Vec<(String, i32)>>,
     pub hp : Option<i32>,
-    pub base_damage : Option<i32>
+    pub base_damage : Option<i32>,
+    pub speed : Option<i32>,
+    // Turns taken to regain each hit point, if it heals at all.
+    pub regen : Option<i32>,
+    // How far an item can be aimed, for items that are, and how far its
+    // effect spreads from where it lands.
+    pub range : Option<i32>,
+    pub radius : Option<i32>,
+    // Shown when the player looks at it.
+    pub description : Option<String>
 }
 
-#[derive(Clone, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum EntityType {
     Enemy, Item
 }
 
-#[derive(Clone, Deserialize, Debug)]
+// Like the campaign, read once when a run starts and kept as a resource.
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Templates {
     pub entities : Vec<Template>,
 }
 
 impl Templates {
-    pub fn load() -> Self {
-        let file = File::open("resources/template.ron")
+    pub fn load(path: &str) -> Self {
+        let file = File::open(path)
             .expect("Failed opening file");
         from_reader(file).expect("Unable to load templates")
     }
@@ -38,43 +48,57 @@ impl Templates {
         &self,
         ecs: &mut World,
         rng: &mut RandomNumberGenerator,
-        level: usize,
-        spawn_points: &[Point]
+        template_levels: &[usize],
+        spawn_points: &[Point],
+        log: &mut GameLog
     ) {
         let mut available_entities = Vec::new();
-        self.entities
+        for t in self.entities
             .iter()
-            .filter(|e| e.levels.contains(&level))
-            .for_each(|t| {
-                for _ in 0 .. t.frequency {
-                    available_entities.push(t);
-                }
+            .filter(|e| template_levels.iter().any(|level| e.levels.contains(level)))
+        {
+            for _ in 0 .. t.frequency {
+                available_entities.push(t);
             }
-        );
+        }
 
         let mut commands = CommandBuffer::new(ecs);
-        spawn_points.iter().for_each(|pt| {
+        for pt in spawn_points {
             if let Some(entity) = rng.random_slice_entry(&available_entities) {
-                self.spawn_entity(pt, entity, &mut commands);
+                Self::spawn_entity(*pt, entity, &mut commands, log);
             }
-        });
+        }
         commands.flush(ecs);
     }
 
+    // Spawn the template with the given name, whatever levels it lists.
+    pub fn spawn_named(&self, ecs: &mut World, name: &str, pt: Point, log: &mut GameLog) {
+        if let Some(template) = self.entities.iter().find(|t| t.name == name) {
+            let mut commands = CommandBuffer::new(ecs);
+            Self::spawn_entity(pt, template, &mut commands, log);
+            commands.flush(ecs);
+        } else {
+            log.warn(format!("there is no template named {name}"));
+        }
+    }
+
     fn spawn_entity(
-        &self,
-        pt: &Point,
+        pt: Point,
         template: &Template,
-        commands: &mut CommandBuffer
+        commands: &mut CommandBuffer,
+        log: &mut GameLog
     ) {
         let entity = commands.push((
-            pt.clone(),
+            pt,
             Render{
                 color: ColorPair::new(WHITE, BLACK),
                 glyph: to_cp437(template.glyph)
             },
             Name(template.name.clone())
         ));
+        if let Some(description) = &template.description {
+            commands.add_component(entity, Description(description.clone()));
+        }
         match template.entity_type {
             EntityType::Item => commands.add_component(entity, Item{}),
             EntityType::Enemy => {
@@ -85,21 +109,37 @@ impl Templates {
                     current: template.hp.unwrap(),
                     max: template.hp.unwrap()
                 });
+                commands.add_component(entity, Speed(template.speed.unwrap_or(NORMAL_SPEED)));
+                commands.add_component(entity, Energy(0));
+                if let Some(turns) = template.regen {
+                    commands.add_component(entity, Regeneration::new(turns));
+                }
             }
         }
         if let Some(effects) = &template.provides {
-            effects.iter().for_each(|(provides, n)| {
+            for (provides, n) in effects {
                 match provides.as_str() {
                     "Healing" => commands.add_component(entity,
                         ProvidesHealing{ amount: *n}),
                     "MagicMap" => commands.add_component(entity,
                         ProvidesDungeonMap{}),
+                    "Haste" => commands.add_component(entity,
+                        ProvidesHaste{ turns: *n }),
+                    "Slow" => commands.add_component(entity,
+                        ProvidesSlow{ turns: *n }),
+                    "Damage" => commands.add_component(entity,
+                        ProvidesDamage{ amount: *n }),
                     _ => {
-                        println!("Warning: we don't know how to provide {}"
-                            , provides);
+                        log.warn(format!("we don't know how to provide {provides}"));
                     }
                 }
-            });
+            }
+        }
+        if let Some(range) = template.range {
+            commands.add_component(entity, Ranged{ range });
+        }
+        if let Some(radius) = template.radius {
+            commands.add_component(entity, AreaOfEffect{ radius });
         }
         if let Some(damage) = &template.base_damage {
             commands.add_component(entity, Damage(*damage));
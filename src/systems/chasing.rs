@@ -6,6 +6,8 @@ use crate::prelude::*;
 #[read_component(FieldOfView)]
 #[read_component(Health)]
 #[read_component(Player)]
+#[read_component(Energy)]
+#[read_component(Hunting)]
 pub fn chasing(
     #[resource] map: &Map,
     ecs: &SubWorld,
@@ -13,7 +15,7 @@ pub fn chasing(
 ) {
 
     // Finds only entities with Point positions and ChasingPlayer tags.
-    let mut movers = <(Entity, &Point, &ChasingPlayer, &FieldOfView)>::query();
+    let mut movers = <(Entity, &Point, &ChasingPlayer, &FieldOfView, &Energy, Option<&Hunting>)>::query();
     
     // Lists all entities with Point and Health components.
     let mut positions = <(Entity, &Point, &Health)>::query();
@@ -21,33 +23,37 @@ pub fn chasing(
     // Finds only entities with Point positions and the Player tag and returns the player's position.
     let mut player = <(&Point, &Player)>::query();
 
-    let player_pos = player.iter(ecs).nth(0).unwrap().0;
-    let player_idx = map_idx(player_pos.x, player_pos.y);
+    let player_pos = player.iter(ecs).next().unwrap().0;
+    let player_idx = map.point2d_to_index(*player_pos);
     let search_targets = vec![player_idx];
     let dijkstra_map = DijkstraMap::new(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
+        map.width,
+        map.height,
         &search_targets,
         map,
         1024.0
     );
 
-    movers.iter(ecs).for_each(|(entity, pos, _, fov)| {
-        if !fov.visible_tiles.contains(&player_pos) {
+    movers.iter(ecs).for_each(|(entity, pos, _, fov, energy, hunting)| {
+        if energy.0 < ACTION_COST {
             return;
         }
-        let idx = map_idx(pos.x, pos.y);
+        let mut acted = false;
+        // A monster that has heard the player knows where to find them.
+        let knows_where = hunting.is_some() || fov.visible_tiles.contains(player_pos);
+        let idx = map.point2d_to_index(*pos);
         if let Some(destination) = DijkstraMap::find_lowest_exit(
             &dijkstra_map, 
             idx, 
             map
-        )
+        ).filter(|_| knows_where)
         {
-            let distance = DistanceAlg::Pythagoras.distance2d(*pos, *player_pos);
-            let destination  = if distance > 1.2 {
-                map.index_to_point2d(destination)
-            } else {
+            // Attack the player from any tile the map would let the monster
+            // step to them from, diagonals included, rather than by distance.
+            let destination  = if map.is_step(*pos, *player_pos) {
                 *player_pos
+            } else {
+                map.index_to_point2d(destination)
             };
 
             let mut attacked = false;
@@ -62,12 +68,15 @@ pub fn chasing(
                                     attacker: *entity,
                                     victim: *victim
                                 }));
+                            acted = true;
                         }
                         attacked = true;
                 });
             if !attacked {
                 commands.push(((), WantsToMove{entity: *entity, destination}));
+                acted = true;
             }
         }
-    })
+        commands.add_component(*entity, if acted { energy.spent() } else { energy.idle() });
+    });
 }
\ No newline at end of file
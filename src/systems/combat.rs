@@ -1,30 +1,43 @@
 use crate::prelude::*;
 
 #[system] 
+#[allow(clippy::trivially_copy_pass_by_ref)]
 #[read_component(WantsToAttack)] 
 #[read_component(Player)] 
 #[write_component(Health)]
 #[read_component(Damage)]
 #[read_component(Carried)]
+#[read_component(Name)]
 // This code iterates the victims collection you just created. Then it uses if 
 // let to activate only if the victim has health (once again, preventing you 
 // from mind- lessly beating up inanimate objects). It then reduces the 
 // victim’s current health by one. If the victim’s hit points are less than 
 // one, the victim is deleted from the game. Finally, it deletes the WantsToAttack message.
-pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+pub fn combat(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] events: &mut Events,
+    #[resource] wizard: &Wizard
+) {
     let mut attackers = <(Entity, &WantsToAttack)>::query();
     let victims : Vec<(Entity, Entity, Entity)> = attackers
         .iter(ecs)
         .map(|(entity, attack)| (*entity, attack.attacker, attack.victim) )
         .collect();
 
-    victims.iter().for_each(|(message, attacker, victim)| {
+    for (message, attacker, victim) in &victims {
         let is_player = ecs
             .entry_ref(*victim)
             .unwrap()
             .get_component::<Player>()
             .is_ok();
 
+        // Wizard mode can make the player shrug off every blow.
+        if is_player && wizard.invulnerable {
+            commands.remove(*message);
+            continue;
+        }
+
         let base_damage = if let Ok(v) = ecs.entry_ref(*attacker) {
             if let Ok(dmg) = v.get_component::<Damage>() {
                 dmg.0
@@ -42,16 +55,31 @@ pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
 
         let final_damage = base_damage + weapon_damage;
 
-        if let Ok(mut health) = ecs
+        let attacker_info = EventEntity::of(ecs, *attacker);
+        let victim_info = EventEntity::of(ecs, *victim);
+
+        if let Ok(health) = ecs
             .entry_mut(*victim)
             .unwrap()
             .get_component_mut::<Health>()
         {
+            let was_alive = health.current > 0;
             health.current -= final_damage;
+            let died = was_alive && health.current < 1;
+            events.send(GameEvent::DamageDealt {
+                attacker: attacker_info.clone(),
+                victim: victim_info.clone(),
+                amount: final_damage,
+            });
+
+            // Only the blow that takes the victim below one hit point kills it.
+            if died {
+                events.send(GameEvent::EntityDied { victim: victim_info, killer: attacker_info });
+            }
             if health.current < 1 && !is_player {
                 commands.remove(*victim);
             }
         }
         commands.remove(*message);
-    });
+    }
 }
\ No newline at end of file
@@ -0,0 +1,48 @@
+use crate::prelude::*;
+
+// Waiting a few turns goes unnoticed, but rest for longer than this and the
+// level starts to hear it.
+const QUIET_TURNS: i32 = 10;
+const HEARD_ONE_IN: i32 = 30;
+
+// Draw out the monsters of the level while the player rests, so resting
+// isn't free: now and then the nearest monster that isn't already coming
+// hears them and starts hunting them down.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Resting)]
+#[read_component(Enemy)]
+#[read_component(ChasingPlayer)]
+#[read_component(Hunting)]
+#[read_component(Name)]
+pub fn disturb_rest(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] events: &mut Events
+) {
+    let Some((player_pos, resting)) = <(&Point, &Resting)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+    else {
+        return;
+    };
+    if resting.turns <= QUIET_TURNS || rng.range(0, HEARD_ONE_IN) != 0 {
+        return;
+    }
+
+    let nearest = <(Entity, &Point)>::query()
+        .filter(component::<Enemy>() & component::<ChasingPlayer>() & !component::<Hunting>())
+        .iter(ecs)
+        .min_by_key(|(_, pos)| {
+            let delta = **pos - *player_pos;
+            delta.x * delta.x + delta.y * delta.y
+        })
+        .map(|(entity, _)| *entity);
+    if let Some(monster) = nearest {
+        commands.add_component(monster, Hunting);
+        events.send(GameEvent::RestDisturbed { monster: EventEntity::of(ecs, monster) });
+    }
+}
@@ -1,29 +1,49 @@
 use crate::prelude::*;
 
 #[system]
-#[read_component(Health)]
+#[write_component(Health)]
+#[write_component(Regeneration)]
 #[read_component(Point)]
 #[read_component(Player)]
 #[read_component(AmuletOfYala)]
+#[read_component(Enemy)]
+#[read_component(Speed)]
+#[write_component(Energy)]
+#[write_component(SpeedEffect)]
 pub fn end_turn(
-    ecs: &SubWorld, 
+    ecs: &mut SubWorld, 
+    commands: &mut CommandBuffer,
     #[resource] turn_state: &mut TurnState,
     #[resource] map: &Map,
+    #[resource] stats: &mut RunStats,
+    #[resource] events: &mut Events,
 ) {
-    let mut player_hp = <(&Health, &Point)>::query().filter(component::<Player>());
-    let mut amulet = <&Point>::query().filter(component::<AmuletOfYala>());
-    let current_state = turn_state.clone();
+    let current_state = *turn_state;
     let mut new_state = match turn_state {
         TurnState::AwaitingInput => return,
-        TurnState::PlayerTurn => TurnState::MonsterTurn,
-        TurnState::MonsterTurn => TurnState::AwaitingInput,
+        TurnState::PlayerTurn => {
+            stats.turns += 1;
+            if let Some(player) = <&Player>::query().iter(ecs).next() {
+                *stats.level_turns.entry(player.map_level).or_insert(0) += 1;
+            }
+            <&mut Energy>::query()
+                .filter(component::<Player>())
+                .iter_mut(ecs)
+                .for_each(|energy| energy.0 -= ACTION_COST);
+            next_to_act(ecs, commands)
+        }
+        // Monsters pay for their own actions as they choose them.
+        TurnState::MonsterTurn => next_to_act(ecs, commands),
         _ => current_state
     };
 
+    let mut player_hp = <(&Health, &Point)>::query().filter(component::<Player>());
+    let mut amulet = <&Point>::query().filter(component::<AmuletOfYala>());
+
     let amulet_default = Point::new(-1, -1);
     let amulet_pos = amulet
         .iter(ecs)
-        .nth(0)
+        .next()
         .unwrap_or(&amulet_default);
 
     player_hp.iter(ecs).for_each(|(hp, pos)| {
@@ -37,10 +57,117 @@ pub fn end_turn(
         if map.tiles[idx] == TileType::Exit {
             new_state = TurnState::NextLevel;
         }
+        if map.tiles[idx] == TileType::UpStairs {
+            new_state = TurnState::PreviousLevel;
+        }
     });
 
+    if new_state == TurnState::Victory && current_state != TurnState::Victory {
+        events.send(GameEvent::Victory);
+    }
+
     // Set the turn resource to the chosen value. The asterisk 
     // (*) de-references the variable, allowing you to write 
     // directly to the stored resource.
     *turn_state = new_state;
-}
\ No newline at end of file
+}
+
+// Let time pass, with everything gaining energy at its own speed, until
+// someone has enough to act. The player goes first when tied, so at equal
+// speeds the player and the monsters take turns.
+fn next_to_act(ecs: &mut SubWorld, commands: &mut CommandBuffer) -> TurnState {
+    loop {
+        let ready = |energy: &Energy| energy.0 >= ACTION_COST;
+        if <&Energy>::query().filter(component::<Player>()).iter(ecs).any(ready) {
+            return TurnState::AwaitingInput;
+        }
+        if <&Energy>::query().filter(component::<Enemy>()).iter(ecs).any(ready) {
+            return TurnState::MonsterTurn;
+        }
+
+        <(Entity, &Speed, &mut Energy, Option<&mut SpeedEffect>)>::query()
+            .iter_mut(ecs)
+            .for_each(|(entity, speed, energy, effect)| {
+                energy.0 += speed.effective(effect.as_deref());
+                if let Some(effect) = effect {
+                    effect.turns -= 1;
+                    if effect.turns == 0 {
+                        commands.remove_component::<SpeedEffect>(*entity);
+                    }
+                }
+            });
+
+        // Wounds heal with time, whatever speed their owner moves at. The
+        // dead stay dead.
+        <(&mut Health, &mut Regeneration)>::query()
+            .iter_mut(ecs)
+            .filter(|(health, _)| health.current > 0 && health.current < health.max)
+            .for_each(|(health, regeneration)| {
+                regeneration.progress += 1;
+                if regeneration.progress >= regeneration.turns {
+                    regeneration.progress = 0;
+                    health.current += 1;
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Play a player and one monster for a number of player turns, the
+    // monster paying for an action on each of its turns as chasing does, or
+    // standing idle. Returns how many turns the monster had between each of
+    // the player's.
+    fn monster_turns(speed: i32, acts: impl Fn(usize) -> bool, player_turns: usize) -> Vec<usize> {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Map::new(10, 10));
+        resources.insert(RunStats::default());
+        resources.insert(Events::default());
+        resources.insert(TurnState::PlayerTurn);
+        ecs.push((Player { map_level: 0 }, Point::new(1, 1), Health { current: 10, max: 10 },
+            Speed(NORMAL_SPEED), Energy(ACTION_COST)));
+        let monster = ecs.push((Enemy, Speed(speed), Energy(0)));
+        let mut schedule = Schedule::builder().add_system(end_turn_system()).build();
+
+        let mut turns = vec![0];
+        loop {
+            match *resources.get::<TurnState>().unwrap() {
+                TurnState::AwaitingInput if turns.len() == player_turns => break,
+                TurnState::AwaitingInput => turns.push(0),
+                TurnState::PlayerTurn => {}
+                TurnState::MonsterTurn => {
+                    let player_turn = turns.len() - 1;
+                    turns[player_turn] += 1;
+                    assert!(turns[player_turn] < 100, "the monster never gives way");
+                    let mut entry = ecs.entry(monster).unwrap();
+                    let energy = entry.get_component_mut::<Energy>().unwrap();
+                    *energy = if acts(player_turn) { energy.spent() } else { energy.idle() };
+                }
+                state => panic!("unexpected {state:?}"),
+            }
+            if *resources.get::<TurnState>().unwrap() == TurnState::AwaitingInput {
+                resources.insert(TurnState::PlayerTurn);
+            }
+            schedule.execute(&mut ecs, &mut resources);
+        }
+        turns
+    }
+
+    #[test]
+    fn speed_sets_how_often_monsters_act() {
+        assert_eq!(monster_turns(NORMAL_SPEED, |_| true, 5), vec![0, 1, 1, 1, 1]);
+        assert_eq!(monster_turns(NORMAL_SPEED * 2, |_| true, 5), vec![0, 2, 2, 2, 2]);
+        assert_eq!(monster_turns(NORMAL_SPEED / 2, |_| true, 5), vec![0, 0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn idle_monsters_pay_nothing_but_bank_nothing() {
+        // Waiting doesn't stall the game, and a monster that stood idle for
+        // a while acts no more often than usual once it has something to do.
+        assert_eq!(monster_turns(NORMAL_SPEED, |_| false, 4), vec![0, 1, 1, 1]);
+        assert_eq!(monster_turns(NORMAL_SPEED * 2, |turn| turn >= 3, 6), vec![0, 1, 1, 2, 2, 2]);
+    }
+}
@@ -13,13 +13,13 @@ pub fn entity_render(
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(1);
-    let offset = Point::new(camera.left_x, camera.top_y);
+    let offset = camera.offset();
 
-    let player_fov = fov.iter(ecs).nth(0).unwrap();
+    let player_fov = fov.iter(ecs).next().unwrap();
 
     renderables.
         iter(ecs)
-        .filter(|(pos, _)| player_fov.visible_tiles.contains(&pos))
+        .filter(|(pos, _)| player_fov.visible_tiles.contains(pos))
         .for_each(|(pos, render)| {
             draw_batch.set(
                 *pos - offset,
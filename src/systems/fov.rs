@@ -11,7 +11,7 @@ pub fn fov(
     views
         .iter_mut(ecs)
         .filter(|(_, fov)| fov.is_dirty)
-        .for_each(|(pos, mut fov)| {
+        .for_each(|(pos, fov)| {
             fov.visible_tiles = field_of_view_set(*pos, fov.radius, map);
             fov.is_dirty = false;
         }
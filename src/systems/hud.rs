@@ -1,12 +1,20 @@
 use crate::prelude::*;
 
 #[system]
+#[allow(clippy::trivially_copy_pass_by_ref)]
 #[read_component(Health)]
 #[read_component(Player)]
 #[read_component(Item)]
 #[read_component(Carried)]
 #[read_component(Name)]
-pub fn hud(ecs: &SubWorld) {
+#[read_component(SpeedEffect)]
+pub fn hud(
+    ecs: &SubWorld,
+    #[resource] seed: &Seed,
+    #[resource] log: &GameLog,
+    #[resource] achievements: &Achievements,
+    #[resource] stats: &RunStats
+) {
     let mut health_query = <&Health>::query()
         .filter(component::<Player>());
     
@@ -16,12 +24,15 @@ pub fn hud(ecs: &SubWorld) {
         // It retrieves a single entry from an iterator as an option. 
         // You know that there is a player, so it’s safe to unwrap() 
         // the option—extracting the player’s health component.
-        .nth(0)
+        .next()
         .unwrap();
 
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(2);
-    draw_batch.print_centered(1, "Explore the Dungeon. Cursor keys to move");
+    // Kept short enough to stay clear of the level on the right of this row
+    // and of the wizard help and seed on the next.
+    draw_batch.print_centered(1, "Move: cursor keys, numpad or hjklyubn  G: get  .: wait  R: rest  \
+        X: explore  E: look  Z: zoom  P: log  Escape: menu");
     draw_batch.bar_horizontal(
         Point::zero(), 
         SCREEN_WIDTH*2, 
@@ -40,7 +51,8 @@ pub fn hud(ecs: &SubWorld) {
     
     let (player, map_level) = <(Entity, &Player)>::query()
         .iter(ecs)
-        .find_map(|(entity, player)| Some((*entity, player.map_level)))
+        .map(|(entity, player)| (*entity, player.map_level))
+        .next()
         .unwrap();
 
     draw_batch.print_color_right(
@@ -48,6 +60,24 @@ pub fn hud(ecs: &SubWorld) {
         format!("Dungeon Level: {}", map_level+1),
         ColorPair::new(YELLOW, BLACK)
     );
+    draw_batch.print_color_right(
+        Point::new(SCREEN_WIDTH*2, 2),
+        format!("Seed: {}", seed.0),
+        ColorPair::new(GRAY, BLACK)
+    );
+
+    let speed_effect = <&SpeedEffect>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .find(|effect| effect.turns > 0);
+    if let Some(effect) = speed_effect {
+        draw_batch.print_color_right(
+            Point::new(SCREEN_WIDTH*2, 3),
+            format!("{} ({})",
+                if effect.percent > 100 { "Hasted" } else { "Slowed" }, effect.turns),
+            ColorPair::new(CYAN, BLACK)
+        );
+    }
 
     let mut item_query = <(&Item, &Name, &Carried)>::query();
     let mut y = 3;
@@ -67,5 +97,22 @@ pub fn hud(ecs: &SubWorld) {
             ColorPair::new(YELLOW, BLACK)
         );
     }
+
+    if let Some((name, turn)) = &achievements.notice {
+        if stats.turns < turn + NOTICE_TURNS {
+            draw_batch.print_color_centered(
+                4,
+                format!(" Achievement unlocked: {name} "),
+                ColorPair::new(BLACK, GOLD)
+            );
+        }
+    }
+
+    // The latest messages, above the bottom two lines that replays use.
+    let mut y = SCREEN_HEIGHT*2 - 3;
+    for entry in log.latest(HUD_LOG_LINES).iter().rev() {
+        draw_batch.print_color(Point::new(1, y), &entry.text, ColorPair::new(entry.color, BLACK));
+        y -= 1;
+    }
     draw_batch.submit(10000).expect("batch error");
 }
\ No newline at end of file
@@ -0,0 +1,52 @@
+use crate::prelude::*;
+
+// Describe what happened this turn in the message log.
+#[system]
+pub fn log_events(#[resource] events: &Events, #[resource] log: &mut GameLog) {
+    for event in events.iter() {
+        match event {
+            GameEvent::DamageDealt { attacker, victim, amount } => {
+                if victim.is_player {
+                    log.add(format!("The {} hits you for {amount}.", attacker.name), RED);
+                } else if attacker.is_player {
+                    log.add(format!("You hit the {} for {amount}.", victim.name), WHITE);
+                }
+            }
+            GameEvent::EntityDied { victim, .. } => {
+                if victim.is_player {
+                    log.add("You die.", RED);
+                } else {
+                    log.add(format!("The {} dies.", victim.name), YELLOW);
+                }
+            }
+            GameEvent::ItemPickedUp { by, item } if by.is_player => {
+                log.add(format!("You pick up the {}.", item.name), CYAN);
+            }
+            GameEvent::ItemUsed { by, item } if by.is_player => {
+                log.add(format!("You use the {}.", item.name), GREEN);
+            }
+            GameEvent::SpeedChanged { entity, percent, .. } => {
+                let faster = *percent > 100;
+                if entity.is_player {
+                    log.add(format!("You feel yourself {}.",
+                        if faster { "speed up" } else { "slow down" }), CYAN);
+                } else {
+                    log.add(format!("The {} {}.", entity.name,
+                        if faster { "speeds up" } else { "slows down" }), CYAN);
+                }
+            }
+            // The player doesn't see what's coming, so it goes unnamed.
+            GameEvent::RestDisturbed { .. } => {
+                log.add("You hear something stirring in the distance.", ORANGE);
+            }
+            GameEvent::MapRevealed => {
+                log.add("The layout of the level is revealed to you.", CYAN);
+            }
+            GameEvent::LevelEntered { level, going_down } => {
+                log.add(format!("You {} to dungeon level {}.",
+                    if *going_down { "descend" } else { "climb" }, level + 1), YELLOW);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::prelude::*;
+
+// Look mode's keys: the movement keys move the cursor instead of the
+// player, Tab jumps to the next thing worth looking at, nearest first, and
+// Escape or E goes back to the game. Nothing here takes a turn.
+#[system]
+#[allow(clippy::ref_option, clippy::trivially_copy_pass_by_ref)]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Name)]
+#[read_component(FieldOfView)]
+pub fn look(
+    ecs: &SubWorld,
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] look: &mut Look,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] turn_state: &mut TurnState
+) {
+    let Some(key) = *key else {
+        return;
+    };
+    match key {
+        VirtualKeyCode::Escape | VirtualKeyCode::E => *turn_state = TurnState::AwaitingInput,
+        VirtualKeyCode::Tab => look.next_target(&targets(ecs, map, camera)),
+        _ => if let Some(delta) = key_direction(key) {
+            look.move_by(delta, camera, map);
+        },
+    }
+}
+
+// Whatever the player can see in view, and the stairs they know of, from
+// the nearest to the player outwards.
+fn targets(ecs: &SubWorld, map: &Map, camera: &Camera) -> Vec<Point> {
+    let (player_pos, fov) = <(&Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+    let mut targets: Vec<Point> = <&Point>::query()
+        .filter(component::<Name>() & !component::<Player>())
+        .iter(ecs)
+        .filter(|pos| fov.visible_tiles.contains(pos))
+        .copied()
+        .collect();
+    targets.extend((0..map.tiles.len())
+        .filter(|idx| map.revealed_tiles[*idx]
+            && matches!(map.tiles[*idx], TileType::Exit | TileType::UpStairs))
+        .map(|idx| map.index_to_point2d(idx)));
+
+    targets.retain(|pt| camera.contains(*pt));
+    targets.sort_by_key(|pt| {
+        let delta = *pt - *player_pos;
+        (delta.x * delta.x + delta.y * delta.y, pt.y, pt.x)
+    });
+    targets.dedup();
+    targets
+}
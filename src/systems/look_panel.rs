@@ -0,0 +1,195 @@
+use crate::prelude::*;
+
+const PANEL_WIDTH: i32 = 40;
+const PANEL_TOP: i32 = 6;
+
+// Highlight the tile under the look cursor and describe it in a panel on
+// the side of the screen away from it: whatever the player can see there,
+// or the tile itself, from memory if it's out of sight.
+#[system]
+#[allow(clippy::borrowed_box, clippy::trivially_copy_pass_by_ref)]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(Item)]
+#[read_component(Name)]
+#[read_component(Description)]
+#[read_component(Health)]
+#[read_component(Damage)]
+#[read_component(Weapon)]
+#[read_component(Carried)]
+#[read_component(FieldOfView)]
+pub fn look_panel(
+    ecs: &SubWorld,
+    #[resource] look: &Look,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] theme: &Box<dyn MapTheme>
+) {
+    let fov = <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+    let lines = if fov.visible_tiles.contains(&look.cursor) {
+        describe_visible(ecs, look.cursor, map, theme.as_ref())
+    } else if map.try_idx(look.cursor).is_some_and(|idx| map.revealed_tiles[idx]) {
+        let (name, description) = theme.describe_tile(map.tiles[map.point2d_to_index(look.cursor)]);
+        let mut lines = vec![(name.to_string(), YELLOW)];
+        lines.extend(wrap(description, WHITE));
+        lines.push((String::new(), WHITE));
+        lines.extend(wrap("You remember this from before. It's out of sight now.", GRAY));
+        lines
+    } else {
+        vec![
+            ("Unexplored".to_string(), YELLOW),
+            ("You haven't seen what's here.".to_string(), WHITE),
+        ]
+    };
+
+    // Keep the panel clear of the cursor.
+    let cursor_text = camera.text_pos(look.cursor);
+    let x = if cursor_text.x < SCREEN_WIDTH { SCREEN_WIDTH*2 - PANEL_WIDTH - 1 } else { 1 };
+    let height = i32::try_from(lines.len()).unwrap_or(0) + 3;
+    let panel = Rect::with_size(x, PANEL_TOP, PANEL_WIDTH, height);
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(0);
+    draw_batch.set_bg(look.cursor - camera.offset(), DARK_GOLDENROD);
+
+    // The text console has no background of its own, so the map under the
+    // panel is blanked out.
+    let cells = camera.text_cells();
+    let covered = Rect::with_exact(panel.x1 / cells, panel.y1 / cells,
+        panel.x2 / cells + 1, panel.y2 / cells + 1);
+    for console in [0, 1] {
+        draw_batch.target(console);
+        draw_batch.fill_region(covered, ColorPair::new(BLACK, BLACK), to_cp437(' '));
+    }
+
+    draw_batch.target(2);
+    draw_batch.draw_double_box(panel, ColorPair::new(WHITE, BLACK));
+    for (y, (text, color)) in (PANEL_TOP + 1..).zip(lines.iter()) {
+        draw_batch.print_color(Point::new(x + 2, y), text, ColorPair::new(*color, BLACK));
+    }
+    draw_batch.print_color(
+        Point::new(x + 2, panel.y2 - 1),
+        "Tab: next  Escape: done",
+        ColorPair::new(GRAY, BLACK)
+    );
+    draw_batch.submit(10300).expect("Batch error");
+}
+
+// The first thing the player can see at the position, monsters before
+// themselves before items, in full, then whatever else is there and the
+// tile under it all.
+fn describe_visible<S: EntityStore>(
+    ecs: &S,
+    pos: Point,
+    map: &Map,
+    theme: &dyn MapTheme
+) -> Vec<(String, (u8, u8, u8))> {
+    let (tile_name, tile_description) = theme.describe_tile(map.tiles[map.point2d_to_index(pos)]);
+    // The player has no name of their own.
+    let mut here: Vec<(Entity, &str)> = <(Entity, &Point, Option<&Name>)>::query()
+        .filter(component::<Name>() | component::<Player>())
+        .iter(ecs)
+        .filter(|(_, entity_pos, _)| **entity_pos == pos)
+        .map(|(entity, _, name)| (*entity, name.map_or("You", |name| name.0.as_str())))
+        .collect();
+    here.sort_by_key(|(entity, _)| {
+        let entry = ecs.entry_ref(*entity).unwrap();
+        if entry.get_component::<Enemy>().is_ok() {
+            0
+        } else if entry.get_component::<Player>().is_ok() {
+            1
+        } else {
+            2
+        }
+    });
+
+    let Some((entity, name)) = here.first() else {
+        let mut lines = vec![(tile_name.to_string(), YELLOW)];
+        lines.extend(wrap(tile_description, WHITE));
+        return lines;
+    };
+    let entry = ecs.entry_ref(*entity).unwrap();
+    let mut lines = vec![((*name).to_string(), YELLOW)];
+    if let Ok(health) = entry.get_component::<Health>() {
+        lines.push((format!("Health: {} / {}", health.current, health.max), WHITE));
+    }
+
+    // Damage is counted the way combat counts it, with any weapon carried.
+    let weapons: Vec<(&str, i32)> = <(&Carried, &Name, &Damage)>::query()
+        .filter(component::<Weapon>())
+        .iter(ecs)
+        .filter(|(carried, _, _)| carried.0 == *entity)
+        .map(|(_, name, damage)| (name.0.as_str(), damage.0))
+        .collect();
+    let damage = entry.get_component::<Damage>().map_or(0, |damage| damage.0)
+        + weapons.iter().map(|(_, damage)| damage).sum::<i32>();
+    if damage > 0 {
+        let label = if entry.get_component::<Item>().is_ok() { "Damage bonus" } else { "Damage" };
+        lines.push((format!("{label}: {damage}"), WHITE));
+    }
+    for (weapon, _) in &weapons {
+        lines.push((format!("Wielding: {weapon}"), WHITE));
+    }
+    if let Ok(description) = entry.get_component::<Description>() {
+        lines.push((String::new(), WHITE));
+        lines.extend(wrap(&description.0, WHITE));
+    }
+
+    lines.push((String::new(), WHITE));
+    if here.len() > 1 {
+        let others: Vec<&str> = here[1..].iter().map(|(_, name)| *name).collect();
+        lines.extend(wrap(&format!("Also here: {}.", others.join(", ")), GRAY));
+    }
+    lines.push((format!("On: {tile_name}"), GRAY));
+    lines
+}
+
+// Break text into lines that fit inside the panel.
+fn wrap(text: &str, color: (u8, u8, u8)) -> Vec<(String, (u8, u8, u8))> {
+    let width = usize::try_from(PANEL_WIDTH - 4).unwrap_or(0);
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + word.len() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines.into_iter().map(|line| (line, color)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(lines: &[(String, (u8, u8, u8))]) -> Vec<&str> {
+        lines.iter().map(|(text, _)| text.as_str()).collect()
+    }
+
+    #[test]
+    fn the_player_is_described_after_monsters_and_before_items() {
+        let mut ecs = World::default();
+        spawn_player(&mut ecs, Point::new(2, 2));
+        ecs.push((Item, Point::new(2, 2), Name("Healing Potion".to_string())));
+        let map = Map::new(10, 10);
+        let theme = ThemeKind::Dungeon.build();
+
+        let lines = describe_visible(&ecs, Point::new(2, 2), &map, theme.as_ref());
+        let lines = names(&lines);
+        assert_eq!(lines[0], "You");
+        assert!(lines.iter().any(|line| line.starts_with("An adventurer")));
+        assert!(lines.contains(&"Also here: Healing Potion."));
+
+        ecs.push((Enemy, Point::new(2, 2), Name("Goblin".to_string())));
+        let lines = describe_visible(&ecs, Point::new(2, 2), &map, theme.as_ref());
+        assert_eq!(names(&lines)[0], "Goblin");
+        assert!(names(&lines).iter().any(|line| line.starts_with("Also here: You")));
+    }
+}
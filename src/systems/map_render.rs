@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 #[system]
+#[allow(clippy::borrowed_box)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
 pub fn map_render(
@@ -13,16 +14,16 @@ pub fn map_render(
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(0);
 
-    let player_fov = fov.iter(ecs).nth(0).unwrap();
+    let player_fov = fov.iter(ecs).next().unwrap();
 
-    for y in camera.top_y ..= camera.bottom_y {
+    let offset = camera.offset();
+    for y in camera.top_y .. camera.bottom_y {
         for x in camera.left_x .. camera.right_x {
             let pt = Point::new(x, y);
-            let offset = Point::new(camera.left_x, camera.top_y);
-            let idx = map_idx(x, y);
-            if map.in_bounds(pt) && (player_fov.visible_tiles.contains(&pt) 
-                | map.revealed_tiles[idx]) 
-            {
+            let Some(idx) = map.try_idx(pt) else {
+                continue;
+            };
+            if player_fov.visible_tiles.contains(&pt) | map.revealed_tiles[idx] {
                 let tint = if player_fov.visible_tiles.contains(&pt) {
                     WHITE
                 } else {
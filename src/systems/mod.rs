@@ -1,20 +1,31 @@
 mod chasing;
 mod combat;
+mod disturb_rest;
 mod end_turn;
 mod entity_render;
 mod fov;
 mod hud;
+mod log_events;
+mod look;
+mod look_panel;
 mod map_render;
 mod movement;
 mod player_input;
 mod random_moves;
+mod record_stats;
+mod targeting;
+mod targeting_overlay;
 mod tooltips;
+mod track_achievements;
+mod travel;
 mod use_items;
+mod wizard_overlay;
 
 use crate::prelude::*;
 
 pub fn build_input_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(travel::travel_system())
         .add_system(player_input::player_input_system())
         .add_system(fov::fov_system())
         .flush()
@@ -22,12 +33,18 @@ pub fn build_input_scheduler() -> Schedule {
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
         .add_system(tooltips::tooltips_system())
+        .add_system(wizard_overlay::wizard_overlay_system())
+        .flush()
+        .add_system(log_events::log_events_system())
+        .add_system(record_stats::record_stats_system())
+        .add_system(track_achievements::track_achievements_system())
         .build()
 }
 
 pub fn build_player_scheduler() -> Schedule {
     Schedule::builder()
     .add_system(use_items::use_items_system())
+        .add_system(disturb_rest::disturb_rest_system())
         .add_system(combat::combat_system())
         .flush()
         .add_system(movement::movement_system())
@@ -39,6 +56,11 @@ pub fn build_player_scheduler() -> Schedule {
         .add_system(end_turn::end_turn_system())
         .add_system(hud::hud_system())
         .add_system(tooltips::tooltips_system())
+        .add_system(wizard_overlay::wizard_overlay_system())
+        .flush()
+        .add_system(log_events::log_events_system())
+        .add_system(record_stats::record_stats_system())
+        .add_system(track_achievements::track_achievements_system())
         .build()
 }
 
@@ -58,5 +80,36 @@ pub fn build_monster_scheduler() -> Schedule {
         .add_system(end_turn::end_turn_system())
         .add_system(hud::hud_system())
         .add_system(tooltips::tooltips_system())
+        .add_system(wizard_overlay::wizard_overlay_system())
+        .flush()
+        .add_system(log_events::log_events_system())
+        .add_system(record_stats::record_stats_system())
+        .add_system(track_achievements::track_achievements_system())
+        .build()
+}
+
+// Look mode takes no turns, it only moves its cursor and draws the game
+// with the panel on top.
+pub fn build_look_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(look::look_system())
+        .flush()
+        .add_system(map_render::map_render_system())
+        .add_system(entity_render::entity_render_system())
+        .add_system(hud::hud_system())
+        .add_system(look_panel::look_panel_system())
+        .build()
+}
+
+// Aiming an item takes no turn until it's used: the cursor moves and the
+// game is drawn with the reach of the item on top.
+pub fn build_targeting_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(targeting::targeting_system())
+        .flush()
+        .add_system(map_render::map_render_system())
+        .add_system(entity_render::entity_render_system())
+        .add_system(hud::hud_system())
+        .add_system(targeting_overlay::targeting_overlay_system())
         .build()
-}
\ No newline at end of file
+}
@@ -1,6 +1,8 @@
 use crate::prelude::*;
 
 #[system(for_each)]
+#[allow(clippy::trivially_copy_pass_by_ref)]
+#[read_component(Point)]
 #[read_component(Player)]
 #[read_component(FieldOfView)]
 pub fn movement(
@@ -8,10 +10,15 @@ pub fn movement(
     want_move: &WantsToMove,
     #[resource] map: &mut Map,
     #[resource] camera: &mut Camera,
+    #[resource] events: &mut Events,
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer
 ) {
-    if map.can_enter_tile(want_move.destination) {
+    // Whatever asked for the move, it has to be a step the map allows from
+    // where the mover stands.
+    let from = ecs.entry_ref(want_move.entity).ok()
+        .and_then(|entry| entry.get_component::<Point>().ok().copied());
+    if from.is_some_and(|from| map.is_step(from, want_move.destination)) {
         commands.add_component(want_move.entity, want_move.destination);
 
         if let Ok(entry) = ecs.entry_ref(want_move.entity) {
@@ -20,9 +27,13 @@ pub fn movement(
 
                 if entry.get_component::<Player>().is_ok()
                 {
-                    camera.on_player_move(want_move.destination);
+                    camera.on_player_move(want_move.destination, map);
                     fov.visible_tiles.iter().for_each(|pos| {
-                        map.revealed_tiles[map_idx(pos.x, pos.y)] = true;
+                        let idx = map.point2d_to_index(*pos);
+                        if !map.revealed_tiles[idx] {
+                            map.revealed_tiles[idx] = true;
+                            events.send(GameEvent::TileRevealed { pos: *pos });
+                        }
                     });
                 }
             }
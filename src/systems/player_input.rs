@@ -1,5 +1,3 @@
-use std::collections::btree_map::Iter;
-
 use crate::prelude::*;
 
 // The #[system] line annotates the player_input 
@@ -13,6 +11,7 @@ use crate::prelude::*;
 // Resources manager, and some of the components 
 // you have defined.
 #[system]
+#[allow(clippy::ref_option, clippy::trivially_copy_pass_by_ref)]
 #[read_component(Point)]
 #[read_component(Player)]
 #[read_component(Enemy)]
@@ -20,25 +19,31 @@ use crate::prelude::*;
 #[read_component(Item)]
 #[read_component(Carried)]
 #[read_component(Weapon)]
+#[read_component(Name)]
+#[read_component(Resting)]
+#[read_component(Ranged)]
+#[read_component(AreaOfEffect)]
+#[read_component(FieldOfView)]
 pub fn player_input(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
     #[resource] key : &Option<VirtualKeyCode>,
-    #[resource] turn_state : &mut TurnState
+    #[resource] map : &Map,
+    #[resource] turn_state : &mut TurnState,
+    #[resource] targeting : &mut Option<Targeting>,
+    #[resource] events : &mut Events
 ) {
     let mut players = <(Entity, &Point)>::query().filter(component::<Player>());
     let mut enemies = <(Entity, &Point)>::query().filter(component::<Enemy>());
 
     if let Some(key) = *key {
         let delta = match key {
-            VirtualKeyCode::Left => Point::new(-1, 0),
-            VirtualKeyCode::Right => Point::new(1, 0),
-            VirtualKeyCode::Up => Point::new(0, -1),
-            VirtualKeyCode::Down => Point::new(0, 1),
+            VirtualKeyCode::Period | VirtualKeyCode::Numpad5 => Point::zero(),
             VirtualKeyCode::G => {
                 let (player, player_pos) = players
                     .iter(ecs)
-                    .find_map(|(entity, pos)| Some((*entity, *pos)))
+                    .map(|(entity, pos)| (*entity, *pos))
+                    .next()
                     .unwrap();
 
                 let mut items = <(Entity, &Item, &Point)>::query();
@@ -49,38 +54,63 @@ pub fn player_input(
                         commands.add_component(*entity, Carried(player));
 
                         if let Ok(e) = ecs.entry_ref(*entity) {
+                            events.send(GameEvent::ItemPickedUp {
+                                by: EventEntity::of(ecs, player),
+                                item: EventEntity::of(ecs, *entity),
+                            });
                             if e.get_component::<Weapon>().is_ok() {
                                 <(Entity, &Carried, &Weapon)>::query()
                                 .iter(ecs)
                                 .filter(|(_, c, _)| c.0 == player)
-                                .for_each(|(e, c, w)| {
+                                .for_each(|(e, _, _)| {
                                     commands.remove(*e);
-                                })
+                                });
                             }
                         }
                     }
                 );
                 Point::new(0, 0)
             },
-            VirtualKeyCode::Key1 => use_item(0, ecs, commands),
-            VirtualKeyCode::Key2 => use_item(1, ecs, commands),
-            VirtualKeyCode::Key3 => use_item(2, ecs, commands),
-            VirtualKeyCode::Key4 => use_item(3, ecs, commands),
-            VirtualKeyCode::Key5 => use_item(4, ecs, commands),
-            VirtualKeyCode::Key6 => use_item(5, ecs, commands),
-            VirtualKeyCode::Key7 => use_item(6, ecs, commands),
-            VirtualKeyCode::Key8 => use_item(7, ecs, commands),
-            VirtualKeyCode::Key9 => use_item(8, ecs, commands),
-            _ => Point::new(0, 0),
+            VirtualKeyCode::Key1 => use_item(0, ecs, commands, map, targeting),
+            VirtualKeyCode::Key2 => use_item(1, ecs, commands, map, targeting),
+            VirtualKeyCode::Key3 => use_item(2, ecs, commands, map, targeting),
+            VirtualKeyCode::Key4 => use_item(3, ecs, commands, map, targeting),
+            VirtualKeyCode::Key5 => use_item(4, ecs, commands, map, targeting),
+            VirtualKeyCode::Key6 => use_item(5, ecs, commands, map, targeting),
+            VirtualKeyCode::Key7 => use_item(6, ecs, commands, map, targeting),
+            VirtualKeyCode::Key8 => use_item(7, ecs, commands, map, targeting),
+            VirtualKeyCode::Key9 => use_item(8, ecs, commands, map, targeting),
+            _ => key_direction(key).unwrap_or_else(Point::zero),
         };
 
-        let (player_entity, destination) = players
+        let (player_entity, player_pos) = players
                 .iter(ecs)
-                .find_map(|(entity, pos)| Some((*entity, *pos + delta)) )
+                .map(|(entity, pos)| (*entity, *pos))
+                .next()
                 .unwrap();
+        let destination = player_pos + delta;
+
+        // An item that has to be aimed waits for a target before the turn
+        // is taken.
+        if targeting.is_some() {
+            *turn_state = TurnState::Targeting;
+            return;
+        }
+
+        // Waiting turn after turn is resting, which the monsters may hear.
+        if matches!(key, VirtualKeyCode::Period | VirtualKeyCode::Numpad5) {
+            let turns = ecs.entry_ref(player_entity).ok()
+                .and_then(|entry| entry.get_component::<Resting>().ok().map(|r| r.turns))
+                .unwrap_or(0);
+            commands.add_component(player_entity, Resting { turns: turns + 1 });
+        } else {
+            commands.remove_component::<Resting>(player_entity);
+        }
 
-        let mut did_something = false;
-        if delta.x !=0 || delta.y != 0 {
+        // A step the map doesn't allow, such as a diagonal around a corner,
+        // neither moves nor attacks, but still takes the turn like walking
+        // into a wall.
+        if (delta.x !=0 || delta.y != 0) && map.is_step(player_pos, destination) {
 
         let mut hit_something = false;
         enemies
@@ -90,7 +120,6 @@ pub fn player_input(
             })
             .for_each(|(entity, _) | {
                 hit_something = true;
-                did_something = true;
 
                 commands
                     .push(((), WantsToAttack{
@@ -100,22 +129,29 @@ pub fn player_input(
             });
 
             if !hit_something {
-                did_something = true;
                 commands
                     .push(((), WantsToMove{
                         entity: player_entity,
                         destination
                     }));
             }
-        };
+        }
         *turn_state = TurnState::PlayerTurn;
     }
 }
 
-fn use_item(n: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer) -> Point {
-    let player_entity = <(Entity, &Player)>::query()
+fn use_item(
+    n: usize,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    map: &Map,
+    targeting: &mut Option<Targeting>
+) -> Point {
+    let (player_entity, player_pos, fov) = <(Entity, &Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
         .iter(ecs)
-        .find_map(|(entity, _player)| Some(*entity))
+        .map(|(entity, pos, fov)| (*entity, *pos, fov.clone()))
+        .next()
         .unwrap();
     
     let item_entity = <(Entity, &Item, &Carried)>::query()
@@ -123,12 +159,39 @@ fn use_item(n: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer) -> Point
         .filter(|(_, _, carried)| carried.0 == player_entity)
         .enumerate()
         .filter(|(item_count, (_, _, _))| *item_count == n)
-        .find_map(|(_, (item_entity, _, _))| Some(*item_entity));
-    if let Some(item_entity) = item_entity {
+        .map(|(_, (item_entity, _, _))| *item_entity)
+        .next();
+    let Some(item_entity) = item_entity else {
+        return Point::zero();
+    };
+    let item = ecs.entry_ref(item_entity).unwrap();
+    if let Ok(ranged) = item.get_component::<Ranged>() {
+        let mut aim = Targeting {
+            item: item_entity,
+            range: ranged.range,
+            radius: item.get_component::<AreaOfEffect>().map_or(0, |area| area.radius),
+            cursor: player_pos,
+        };
+        // Start on the nearest enemy in reach, if there is one, or else on
+        // the player.
+        if let Some(enemy) = <&Point>::query()
+            .filter(component::<Enemy>())
+            .iter(ecs)
+            .filter(|pos| aim.is_valid(**pos, player_pos, &fov.visible_tiles, map))
+            .min_by_key(|pos| {
+                let delta = **pos - player_pos;
+                (delta.x * delta.x + delta.y * delta.y, pos.y, pos.x)
+            })
+        {
+            aim.cursor = *enemy;
+        }
+        *targeting = Some(aim);
+    } else {
         commands
             .push(((), ActivateItem{
                 used_by: player_entity,
                 item: item_entity,
+                target: None,
             }));
     }
     Point::zero()
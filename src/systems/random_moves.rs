@@ -5,27 +5,34 @@ use crate::prelude::*;
 #[read_component(MovingRandomly)]
 #[read_component(Health)]
 #[read_component(Player)]
-pub fn random_move(ecs: &SubWorld, commands: &mut CommandBuffer) {
+#[read_component(Energy)]
+pub fn random_move(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] rng: &mut RandomNumberGenerator
+) {
     
     // Create a new Query with writable access to Point and read-only access to
     // MovingRandomly.
-    let mut movers = <(Entity, &Point, &MovingRandomly)>::query();
+    let mut movers = <(Entity, &Point, &MovingRandomly, &Energy)>::query();
     let mut positions = <(Entity, &Point, &Health)>::query();
     movers
         .iter(ecs)
-        .for_each(| (entity, pos, _) | {
-        let mut rng = RandomNumberGenerator::new();
+        .filter(|(_, _, _, energy)| energy.0 >= ACTION_COST)
+        .for_each(| (entity, pos, _, energy) | {
 
-            // Randomly choose a direction to move and store the delta.
-            // Add position to it to determine the destination.
-            let destination = match rng.range(0, 4) {
-                0 => Point::new(-1, 0),
-                1 => Point::new(1, 0),
-                2 => Point::new(0, -1),
-                _ => Point::new(0, 1),
-            } + *pos;
+            // Randomly choose one of the steps the map's rule allows from
+            // here, as the player could, and add it to the position to
+            // determine the destination.
+            let steps: Vec<Point> = STEPS
+                .into_iter()
+                .filter(|delta| map.steps.allows(*pos, *delta, |pt| map.can_enter_tile(pt)))
+                .collect();
+            let destination = steps[rng.range(0, steps.len())] + *pos;
             
             let mut attacked = false;
+            let mut acted = false;
             positions
                 .iter(ecs)
                 .filter(|(_, target_pos, _)| **target_pos == destination)
@@ -38,6 +45,7 @@ pub fn random_move(ecs: &SubWorld, commands: &mut CommandBuffer) {
                                 attacker: *entity,
                                 victim: *victim
                             }));
+                        acted = true;
                     }
                     attacked = true;
                 }
@@ -46,7 +54,9 @@ pub fn random_move(ecs: &SubWorld, commands: &mut CommandBuffer) {
             if !attacked {
                 commands
                     .push(((), WantsToMove{ entity: *entity, destination }));
+                acted = true;
             }
+            commands.add_component(*entity, if acted { energy.spent() } else { energy.idle() });
     
         }
     );
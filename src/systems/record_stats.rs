@@ -0,0 +1,34 @@
+use crate::prelude::*;
+
+// Keep the run's statistics up to date for the morgue file and high scores.
+#[system]
+pub fn record_stats(#[resource] events: &Events, #[resource] stats: &mut RunStats) {
+    for event in events.iter() {
+        match event {
+            GameEvent::DamageDealt { attacker, victim, amount } => {
+                if victim.is_player {
+                    stats.damage_taken += amount;
+                } else if attacker.is_player {
+                    stats.damage_dealt += amount;
+                }
+            }
+            GameEvent::EntityDied { victim, killer } => {
+                if victim.is_player {
+                    stats.cause_of_death = Some(killer.name.clone());
+                } else if killer.is_player {
+                    *stats.kills.entry(victim.name.clone()).or_insert(0) += 1;
+                }
+            }
+            GameEvent::ItemPickedUp { by, item } if by.is_player => {
+                stats.items_picked_up.push(item.name.clone());
+            }
+            GameEvent::ItemUsed { by, item } if by.is_player => {
+                stats.items_used.push(item.name.clone());
+            }
+            GameEvent::LevelEntered { level, .. } => {
+                stats.deepest_level = stats.deepest_level.max(*level);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::prelude::*;
+
+// Targeting mode's keys: the movement keys move the cursor, Tab jumps to the
+// next enemy in reach, Enter uses the item on the cursor, which takes the
+// turn, and Escape puts it away again without one.
+#[system]
+#[allow(clippy::ref_option, clippy::trivially_copy_pass_by_ref)]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(FieldOfView)]
+pub fn targeting(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] targeting: &mut Option<Targeting>,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] turn_state: &mut TurnState
+) {
+    let (Some(key), Some(aim)) = (*key, targeting.as_mut()) else {
+        return;
+    };
+    let (player, player_pos, fov) = <(Entity, &Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+    match key {
+        VirtualKeyCode::Escape => {
+            *targeting = None;
+            *turn_state = TurnState::AwaitingInput;
+        }
+        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+            // The cursor shows red where the item can't be used, and Enter
+            // does nothing there.
+            if aim.is_valid(aim.cursor, *player_pos, &fov.visible_tiles, map) {
+                commands.push(((), ActivateItem {
+                    used_by: *player,
+                    item: aim.item,
+                    target: Some(aim.cursor),
+                }));
+                *targeting = None;
+                *turn_state = TurnState::PlayerTurn;
+            }
+        }
+        VirtualKeyCode::Tab => {
+            // Enemies in reach, nearest first.
+            let mut targets: Vec<Point> = <&Point>::query()
+                .filter(component::<Enemy>())
+                .iter(ecs)
+                .filter(|pos| aim.is_valid(**pos, *player_pos, &fov.visible_tiles, map))
+                .copied()
+                .collect();
+            targets.sort_by_key(|pt| {
+                let delta = *pt - *player_pos;
+                (delta.x * delta.x + delta.y * delta.y, pt.y, pt.x)
+            });
+            let next = targets.iter()
+                .position(|pt| *pt == aim.cursor)
+                .map_or(0, |i| (i + 1) % targets.len());
+            if let Some(target) = targets.get(next) {
+                aim.cursor = *target;
+            }
+        }
+        _ => if let Some(delta) = key_direction(key) {
+            aim.move_by(delta, camera, map);
+        },
+    }
+}
@@ -0,0 +1,71 @@
+use crate::prelude::*;
+
+// Show where the item being aimed can reach: the tiles it can be aimed at,
+// the line it would travel from the player and the area it would cover,
+// with what's being aimed and the keys to aim it above the map.
+#[system]
+#[allow(clippy::ref_option, clippy::trivially_copy_pass_by_ref)]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(Name)]
+pub fn targeting_overlay(
+    ecs: &SubWorld,
+    #[resource] targeting: &Option<Targeting>,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera
+) {
+    // Nothing is left to show once the item has been used or put away.
+    let Some(aim) = targeting else {
+        return;
+    };
+    let (player_pos, fov) = <(&Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+    let valid = aim.is_valid(aim.cursor, *player_pos, &fov.visible_tiles, map);
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(0);
+    let offset = camera.offset();
+    for pt in &fov.visible_tiles {
+        if camera.contains(*pt) && aim.is_valid(*pt, *player_pos, &fov.visible_tiles, map) {
+            draw_batch.set_bg(*pt - offset, MIDNIGHT_BLUE);
+        }
+    }
+    if valid {
+        for pt in aim.area(map) {
+            if camera.contains(pt) && fov.visible_tiles.contains(&pt) {
+                draw_batch.set_bg(pt - offset, DARK_RED);
+            }
+        }
+    }
+    for pt in line2d_bresenham(*player_pos, aim.cursor).into_iter().skip(1) {
+        if camera.contains(pt) {
+            draw_batch.set_bg(pt - offset, DARK_GOLDENROD);
+        }
+    }
+    draw_batch.set_bg(aim.cursor - offset, if valid { GOLD } else { RED });
+
+    let name = ecs.entry_ref(aim.item).ok()
+        .and_then(|item| item.get_component::<Name>().ok().map(|name| name.0.clone()))
+        .unwrap_or_default();
+    let reach = if aim.radius > 0 {
+        format!("range {}, radius {}", aim.range, aim.radius)
+    } else {
+        format!("range {}", aim.range)
+    };
+    draw_batch.target(2);
+    draw_batch.print_color_centered(
+        6,
+        format!(" Aiming the {name} ({reach}) "),
+        ColorPair::new(BLACK, GOLD)
+    );
+    draw_batch.print_color_centered(
+        7,
+        " Enter: use  Tab: next target  Escape: cancel ",
+        ColorPair::new(GRAY, BLACK)
+    );
+    draw_batch.submit(10300).expect("Batch error");
+}
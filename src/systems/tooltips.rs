@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 #[system]
+#[allow(clippy::trivially_copy_pass_by_ref)]
 #[read_component(Point)]
 #[read_component(Name)]
 #[read_component(FieldOfView)]
@@ -12,18 +13,19 @@ pub fn tooltips(
 ) {
     let mut positions = <(Entity, &Point, &Name)>::query();
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
-    let offset = Point::new(camera.left_x, camera.top_y);
-    let map_pos = *mouse_pos + offset;
+    // The map console has a cell per tile at any zoom, so the mouse
+    // position is in tiles too.
+    let map_pos = *mouse_pos + camera.offset();
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(2);
-    let player_fov = fov.iter(ecs).nth(0).unwrap();
+    let player_fov = fov.iter(ecs).next().unwrap();
     positions
         .iter(ecs)
         .filter(|(_, pos, _)| 
-            **pos == map_pos && player_fov.visible_tiles.contains(&pos) 
+            **pos == map_pos && player_fov.visible_tiles.contains(pos) 
         )
         .for_each(|(entity, _, name) | {
-            let screen_pos = *mouse_pos * 4;
+            let screen_pos = camera.text_pos(map_pos);
             let display = if let Ok(health) = ecs.entry_ref(*entity)
                 .unwrap()
                 .get_component::<Health>() 
@@ -0,0 +1,203 @@
+use crate::prelude::*;
+
+// Record what happened for the achievements, and unlock those whose trigger
+// just happened with all their conditions met. Runs that used wizard mode
+// don't unlock anything.
+#[system]
+#[read_component(Player)]
+#[read_component(Point)]
+#[read_component(Carried)]
+#[read_component(Weapon)]
+#[read_component(Name)]
+pub fn track_achievements(
+    ecs: &SubWorld,
+    #[resource] events: &Events,
+    #[resource] map: &Map,
+    #[resource] stats: &RunStats,
+    #[resource] progress: &mut AchievementProgress,
+    #[resource] achievements: &mut Achievements,
+    #[resource] log: &mut GameLog
+) {
+    for event in events.iter().filter_map(AchievementEvent::from_game_event) {
+        progress.record(&event);
+        if stats.wizard {
+            continue;
+        }
+
+        let unlocked: Vec<String> = achievements.list
+            .iter()
+            .filter(|a| !achievements.unlocked.contains(&a.name))
+            .filter(|a| a.when.matches(&event))
+            .filter(|a| a.conditions.iter().all(|c| holds(c, ecs, map, progress)))
+            .map(|a| a.name.clone())
+            .collect();
+        for name in unlocked {
+            log.add(format!("Achievement unlocked: {name}!"), GOLD);
+            achievements.unlock(&name, stats.turns);
+        }
+    }
+}
+
+fn holds(condition: &Condition, ecs: &SubWorld, map: &Map, progress: &AchievementProgress) -> bool {
+    match condition {
+        Condition::Count { event, scope, at_least, at_most } => {
+            let count = progress.count(event, scope);
+            count >= *at_least && count <= *at_most
+        }
+        Condition::Wielding(weapon) => {
+            let player = <Entity>::query()
+                .filter(component::<Player>())
+                .iter(ecs)
+                .next()
+                .copied();
+            <(&Carried, &Name)>::query()
+                .filter(component::<Weapon>())
+                .iter(ecs)
+                .any(|(carried, name)| Some(carried.0) == player && name.0 == *weapon)
+        }
+        Condition::LevelRevealed => level_revealed(ecs, map),
+    }
+}
+
+// Whether every tile the player could walk to has been seen. Floor sealed
+// off by walls can never be explored, so it doesn't count.
+fn level_revealed(ecs: &SubWorld, map: &Map) -> bool {
+    let Some(player_pos) = <&Point>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+    else {
+        return false;
+    };
+    let start = map.point2d_to_index(*player_pos);
+    let dijkstra_map = DijkstraMap::new(map.width, map.height, &[start], map, 1024.0);
+    dijkstra_map.map
+        .iter()
+        .zip(&map.revealed_tiles)
+        .filter(|(distance, _)| **distance < f32::MAX)
+        .all(|(_, revealed)| *revealed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::use_items::use_items_system;
+
+    struct Run {
+        ecs: World,
+        resources: Resources,
+        player: Entity,
+    }
+
+    impl Run {
+        // The player in the corner of an open room, or of one with a wall
+        // down its middle, with nothing seen yet and the achievements from
+        // the data file, none of them unlocked.
+        fn new(walled_off: bool) -> Self {
+            let mut map = Map::new(10, 10);
+            if walled_off {
+                for y in 0 .. map.height {
+                    let idx = map.point2d_to_index(Point::new(5, y));
+                    map.tiles[idx] = TileType::Wall;
+                }
+            }
+            let list = ron::from_str(include_str!("../../resources/achievements.ron")).unwrap();
+            let mut resources = Resources::default();
+            resources.insert(map);
+            resources.insert(Events::default());
+            resources.insert(RunStats::default());
+            resources.insert(Wizard::default());
+            resources.insert(GameLog::default());
+            resources.insert(AchievementProgress::new(0));
+            resources.insert(Achievements { list, ..Achievements::default() });
+            let mut ecs = World::default();
+            let player = ecs.push((Player { map_level: 0 }, Point::new(1, 1)));
+            Self { ecs, resources, player }
+        }
+
+        fn play(&mut self, events: Vec<GameEvent>) {
+            for event in events {
+                self.resources.get_mut::<Events>().unwrap().send(event);
+            }
+            Schedule::builder()
+                .add_system(use_items_system())
+                .flush()
+                .add_system(track_achievements_system())
+                .build()
+                .execute(&mut self.ecs, &mut self.resources);
+            self.resources.get_mut::<Events>().unwrap().clear();
+        }
+
+        // Reveal the tiles left of the middle, as walking there would.
+        fn explore_west(&mut self) {
+            {
+                let mut map = self.resources.get_mut::<Map>().unwrap();
+                for y in 0 .. map.height {
+                    for x in 0 .. 5 {
+                        let idx = map.point2d_to_index(Point::new(x, y));
+                        map.revealed_tiles[idx] = true;
+                    }
+                }
+            }
+            self.play(vec![GameEvent::TileRevealed { pos: Point::new(4, 9) }]);
+        }
+
+        fn unlocked(&self, name: &str) -> bool {
+            self.resources.get::<Achievements>().unwrap().unlocked.contains(name)
+        }
+    }
+
+    #[test]
+    fn reading_the_map_makes_no_cartographer() {
+        let mut run = Run::new(false);
+        let map = run.ecs.push((Name("Dungeon Map".to_string()), ProvidesDungeonMap));
+        run.ecs.push(((), ActivateItem { used_by: run.player, item: map, target: None }));
+        run.play(Vec::new());
+        assert!(run.resources.get::<Map>().unwrap().revealed_tiles.iter().all(|t| *t));
+        assert!(!run.unlocked("Cartographer"));
+
+        // Nor does anything seen afterwards on the same level.
+        run.play(vec![GameEvent::TileRevealed { pos: Point::new(1, 1) }]);
+        assert!(!run.unlocked("Cartographer"));
+    }
+
+    #[test]
+    fn only_reachable_tiles_need_revealing() {
+        let mut run = Run::new(false);
+        run.explore_west();
+        assert!(!run.unlocked("Cartographer"));
+
+        let mut run = Run::new(true);
+        run.explore_west();
+        assert!(run.unlocked("Cartographer"));
+    }
+
+    #[test]
+    fn climbing_back_up_enters_no_level() {
+        let mut run = Run::new(false);
+        *run.resources.get_mut::<AchievementProgress>().unwrap() = AchievementProgress::new(2);
+        run.play(vec![GameEvent::LevelEntered { level: 1, going_down: false }]);
+        assert!(!run.unlocked("Teetotaller"));
+
+        run.play(vec![GameEvent::LevelEntered { level: 2, going_down: true }]);
+        assert!(run.unlocked("Pacifist"));
+    }
+
+    #[test]
+    fn counts_cover_their_level() {
+        // A potion drunk on level 1 spoils Teetotaller, one drunk later
+        // doesn't.
+        let mut run = Run::new(false);
+        let potion = run.ecs.push((Name("Healing Potion".to_string()),));
+        let used = GameEvent::ItemUsed {
+            by: EventEntity::of(&run.ecs, run.player),
+            item: EventEntity::of(&run.ecs, potion),
+        };
+        run.play(vec![used.clone(), GameEvent::LevelEntered { level: 1, going_down: true }]);
+        assert!(!run.unlocked("Teetotaller"));
+
+        let mut run = Run::new(false);
+        run.play(vec![GameEvent::LevelEntered { level: 1, going_down: true }, used]);
+        assert!(run.unlocked("Teetotaller"));
+    }
+}
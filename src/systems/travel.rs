@@ -0,0 +1,291 @@
+use crate::prelude::*;
+
+// Walk the player towards a clicked tile, explore the level when X is
+// pressed or rest when R is, one turn each time the game waits for input,
+// by pressing the key for that turn. Any other key takes over. The walk
+// stops short when a new enemy comes into view, the player is hurt or
+// something stands in the way; exploring also stops for any enemy in view
+// and on finding an item, and resting for any enemy in view or once the
+// player is healed.
+#[system]
+#[allow(clippy::ref_option)]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(Item)]
+#[read_component(FieldOfView)]
+#[read_component(Health)]
+#[read_component(Name)]
+pub fn travel(
+    ecs: &SubWorld,
+    #[resource] key: &mut Option<VirtualKeyCode>,
+    #[resource] travel: &mut Travel,
+    #[resource] map: &Map,
+    #[resource] log: &mut GameLog
+) {
+    let requested = travel.requested.take();
+    let explore = *key == Some(VirtualKeyCode::X);
+    let rest = *key == Some(VirtualKeyCode::R);
+    if key.is_some() {
+        travel.stop();
+        if !explore && !rest {
+            return;
+        }
+    }
+
+    let (player_pos, fov, health) = <(&Point, &FieldOfView, &Health)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(pos, fov, health)| (*pos, fov, *health))
+        .next()
+        .unwrap();
+    let enemies: Vec<(Entity, Point, &str)> = <(Entity, &Point, &Name)>::query()
+        .filter(component::<Enemy>())
+        .iter(ecs)
+        .filter(|(_, pos, _)| fov.visible_tiles.contains(pos))
+        .map(|(entity, pos, name)| (*entity, *pos, name.0.as_str()))
+        .collect();
+
+    // Pressing X or R takes no turn of its own, only the ones it leads to.
+    if explore || rest {
+        *key = None;
+        if let Some((_, _, name)) = enemies.first() {
+            let action = if explore { "explore" } else { "rest" };
+            log.add(format!("You can't {action} with the {name} in view."), WHITE);
+            return;
+        }
+        if explore {
+            travel.explore(health.current);
+        } else if health.current >= health.max {
+            log.add("You're already at full health.", WHITE);
+            return;
+        } else {
+            travel.rest(health.current);
+        }
+    }
+
+    if let Some(destination) = requested {
+        // Clicking a monster next to the player attacks it.
+        let attack = direction_key(destination - player_pos)
+            .filter(|_| map.is_step(player_pos, destination))
+            .filter(|_| enemies.iter().any(|(_, pos, _)| *pos == destination));
+        if attack.is_some() {
+            travel.stop();
+            *key = attack;
+            return;
+        }
+        if let Some(path) = plan_path(map, &fov.visible_tiles, player_pos, destination) {
+            let enemies_seen = enemies.iter().map(|(entity, _, _)| *entity).collect();
+            travel.start(path, enemies_seen, health.current);
+        }
+    }
+
+    if travel.is_resting() {
+        *key = rest_turn(travel, health, enemies.first().map(|(_, _, name)| *name), log);
+        return;
+    }
+
+    let next = if travel.is_exploring() {
+        let item_here = <(&Point, &Name)>::query()
+            .filter(component::<Item>())
+            .iter(ecs)
+            .find(|(pos, _)| **pos == player_pos)
+            .map(|(_, name)| name.0.as_str());
+        if let (Some(item), true) = (item_here, travel.has_moved()) {
+            log.add(format!("You find the {item} here."), WHITE);
+            travel.stop();
+            return;
+        }
+        if let Some(next) = explore_step(map, &fov.visible_tiles, player_pos) {
+            next
+        } else {
+            travel.stop();
+            log.add(explored_message(map, player_pos), YELLOW);
+            return;
+        }
+    } else if let Some(next) = travel.next_step() {
+        next
+    } else {
+        return;
+    };
+    if let Some((_, _, name)) = enemies.iter().find(|(entity, _, _)| travel.is_new_enemy(*entity)) {
+        log.add(format!("You spot the {name} and stop."), WHITE);
+        travel.stop();
+        return;
+    }
+    if travel.was_hurt(health.current) || enemies.iter().any(|(_, pos, _)| *pos == next) {
+        travel.stop();
+        return;
+    }
+
+    // The path no longer starts beside the player if they were moved some
+    // other way, such as by changing level.
+    if let Some(step) = direction_key(next - player_pos) {
+        travel.take_step(health.current);
+        *key = Some(step);
+    } else {
+        travel.stop();
+    }
+}
+
+// Wait another turn, unless an enemy has shown up, the player was hurt or
+// they are healed.
+fn rest_turn(
+    travel: &mut Travel,
+    health: Health,
+    enemy_in_view: Option<&str>,
+    log: &mut GameLog
+) -> Option<VirtualKeyCode> {
+    if let Some(name) = enemy_in_view {
+        log.add(format!("You spot the {name} and stop resting."), WHITE);
+    } else if health.current >= health.max {
+        log.add("You feel fully rested.", WHITE);
+    } else if !travel.was_hurt(health.current) {
+        travel.take_step(health.current);
+        return Some(VirtualKeyCode::Period);
+    }
+    travel.stop();
+    None
+}
+
+// Once there's nothing left to explore, point the way on, if the player
+// has seen it.
+fn explored_message(map: &Map, player_pos: Point) -> String {
+    let Some(exit) = map.find_tile(TileType::Exit)
+        .filter(|exit| map.revealed_tiles[map.point2d_to_index(*exit)])
+    else {
+        return "There's nothing more to explore here.".to_string();
+    };
+    let delta = exit - player_pos;
+    let north_south = match delta.y.signum() {
+        -1 => "north",
+        1 => "south",
+        _ => "",
+    };
+    let east_west = match delta.x.signum() {
+        -1 => "west",
+        1 => "east",
+        _ => "",
+    };
+    let direction = match (north_south, east_west) {
+        ("", "") => return "There's nothing more to explore here.".to_string(),
+        (direction, "") | ("", direction) => direction.to_string(),
+        (north_south, east_west) => format!("{north_south}-{east_west}"),
+    };
+    format!("There's nothing more to explore here. The exit lies to the {direction}.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A player standing on an open 20x10 level, which they have seen as far
+    // across as the given column, and can see all of.
+    fn level(seen_to_x: i32) -> (World, Resources, Entity) {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        let mut map = Map::new(20, 10);
+        for idx in 0..map.tiles.len() {
+            map.revealed_tiles[idx] = map.index_to_point2d(idx).x < seen_to_x;
+        }
+        let mut fov = FieldOfView::new(8);
+        fov.visible_tiles = (0..map.tiles.len())
+            .map(|idx| map.index_to_point2d(idx))
+            .filter(|pt| pt.x < seen_to_x)
+            .collect();
+        fov.is_dirty = false;
+        let player = ecs.push((Player { map_level: 0 }, Point::new(2, 5), fov,
+            Health { current: 10, max: 10 }));
+        resources.insert(map);
+        resources.insert(Travel::default());
+        resources.insert(GameLog::default());
+        (ecs, resources, player)
+    }
+
+    // Run the system for a turn in which the given key was pressed, take the
+    // step it chose, if any, and return the key it played.
+    fn turn(ecs: &mut World, resources: &mut Resources, player: Entity, key: Option<VirtualKeyCode>)
+        -> Option<VirtualKeyCode>
+    {
+        resources.insert(key);
+        Schedule::builder().add_system(travel_system()).build().execute(ecs, resources);
+        let key = *resources.get::<Option<VirtualKeyCode>>().unwrap();
+        if let Some(delta) = key.and_then(key_direction) {
+            *ecs.entry(player).unwrap().get_component_mut::<Point>().unwrap() += delta;
+        }
+        key
+    }
+
+    fn last_message(resources: &Resources) -> String {
+        resources.get::<GameLog>().unwrap().latest(1)[0].text.clone()
+    }
+
+    fn walk_to(ecs: &mut World, resources: &mut Resources, player: Entity, destination: Point) {
+        resources.get_mut::<Travel>().unwrap().requested = Some(destination);
+        assert_eq!(turn(ecs, resources, player, None), Some(VirtualKeyCode::Right));
+        assert_eq!(turn(ecs, resources, player, None), Some(VirtualKeyCode::Right));
+    }
+
+    #[test]
+    fn a_walk_goes_on_past_enemies_already_in_view() {
+        let (mut ecs, mut resources, player) = level(20);
+        ecs.push((Enemy, Point::new(2, 8), Name("Goblin".to_string())));
+        walk_to(&mut ecs, &mut resources, player, Point::new(15, 5));
+        assert_eq!(turn(&mut ecs, &mut resources, player, None), Some(VirtualKeyCode::Right));
+    }
+
+    #[test]
+    fn a_walk_stops_when_a_new_enemy_comes_into_view() {
+        let (mut ecs, mut resources, player) = level(20);
+        walk_to(&mut ecs, &mut resources, player, Point::new(15, 5));
+
+        ecs.push((Enemy, Point::new(12, 2), Name("Goblin".to_string())));
+        assert_eq!(turn(&mut ecs, &mut resources, player, None), None);
+        assert_eq!(last_message(&resources), "You spot the Goblin and stop.");
+        assert_eq!(turn(&mut ecs, &mut resources, player, None), None);
+    }
+
+    #[test]
+    fn a_walk_stops_when_the_player_is_hurt() {
+        let (mut ecs, mut resources, player) = level(20);
+        walk_to(&mut ecs, &mut resources, player, Point::new(15, 5));
+
+        ecs.entry(player).unwrap().get_component_mut::<Health>().unwrap().current -= 1;
+        assert_eq!(turn(&mut ecs, &mut resources, player, None), None);
+        assert_eq!(turn(&mut ecs, &mut resources, player, None), None);
+    }
+
+    #[test]
+    fn exploring_stops_on_finding_an_item() {
+        let (mut ecs, mut resources, player) = level(10);
+        assert!(turn(&mut ecs, &mut resources, player, Some(VirtualKeyCode::X)).is_some());
+        let pos = *ecs.entry(player).unwrap().get_component::<Point>().unwrap();
+
+        ecs.push((Item, pos, Name("Healing Potion".to_string())));
+        assert_eq!(turn(&mut ecs, &mut resources, player, None), None);
+        assert_eq!(last_message(&resources), "You find the Healing Potion here.");
+        assert!(!resources.get::<Travel>().unwrap().is_exploring());
+    }
+
+    #[test]
+    fn exploring_a_level_seen_in_full_points_to_the_exit() {
+        let (mut ecs, mut resources, player) = level(20);
+        {
+            let mut map = resources.get_mut::<Map>().unwrap();
+            let exit = map.point2d_to_index(Point::new(15, 2));
+            map.tiles[exit] = TileType::Exit;
+        }
+        assert_eq!(turn(&mut ecs, &mut resources, player, Some(VirtualKeyCode::X)), None);
+        assert_eq!(last_message(&resources),
+            "There's nothing more to explore here. The exit lies to the north-east.");
+        assert!(!resources.get::<Travel>().unwrap().is_exploring());
+    }
+
+    #[test]
+    fn exploring_waits_for_the_enemies_in_view() {
+        let (mut ecs, mut resources, player) = level(10);
+        ecs.push((Enemy, Point::new(5, 5), Name("Goblin".to_string())));
+        assert_eq!(turn(&mut ecs, &mut resources, player, Some(VirtualKeyCode::X)), None);
+        assert_eq!(last_message(&resources), "You can't explore with the Goblin in view.");
+    }
+}
@@ -1,27 +1,104 @@
 use crate::prelude::*;
 
 #[system]
+#[allow(clippy::trivially_copy_pass_by_ref)]
 #[read_component(ActivateItem)]
 #[read_component(ProvidesHealing)]
 #[write_component(Health)]
 #[read_component(ProvidesDungeonMap)]
+#[read_component(Player)]
+#[read_component(Name)]
+#[read_component(ProvidesHaste)]
+#[read_component(ProvidesSlow)]
+#[read_component(FieldOfView)]
+#[read_component(Enemy)]
+#[read_component(Point)]
+#[read_component(ProvidesDamage)]
+#[read_component(AreaOfEffect)]
 pub fn use_items(
     ecs: &mut SubWorld, 
     commands: &mut CommandBuffer,
-    #[resource] map: &mut Map
+    #[resource] map: &mut Map,
+    #[resource] events: &mut Events,
+    #[resource] wizard: &Wizard
 ) {
     let mut healing_to_apply = Vec::<(Entity, i32)>::new();
+    let mut damage_to_apply = Vec::<(Entity, Entity, i32)>::new();
     <(Entity, &ActivateItem)>::query().iter(ecs)
     .for_each(|(entity, activate)| {
 
         let item = ecs.entry_ref(activate.item);
         if let Ok(item) = item {
+            events.send(GameEvent::ItemUsed {
+                by: EventEntity::of(ecs, activate.used_by),
+                item: EventEntity::of(ecs, activate.item),
+            });
+
+            // An aimed item affects whatever is in the area where it lands,
+            // anything else its user.
+            let area = activate.target.map(|target| {
+                let radius = item.get_component::<AreaOfEffect>().map_or(0, |area| area.radius);
+                blast_area(target, radius, map)
+            });
+            let targets: Vec<Entity> = if let Some(area) = &area {
+                <(Entity, &Point, &Health)>::query()
+                    .iter(ecs)
+                    .filter(|(_, pos, _)| area.contains(pos))
+                    .map(|(entity, _, _)| *entity)
+                    .collect()
+            } else {
+                vec![activate.used_by]
+            };
+
             if let Ok(healing) = item.get_component::<ProvidesHealing>() {
-                healing_to_apply.push((activate.used_by, healing.amount));
+                for target in &targets {
+                    healing_to_apply.push((*target, healing.amount));
+                }
+            }
+
+            // Whoever throws a blast stands clear of it.
+            if let Ok(damage) = item.get_component::<ProvidesDamage>() {
+                for target in targets.iter().filter(|target| **target != activate.used_by) {
+                    damage_to_apply.push((activate.used_by, *target, damage.amount));
+                }
             }
 
+            // Tiles the map shows weren't explored, so they don't count as
+            // revealed one by one.
             if let Ok(_mapper) = item.get_component::<ProvidesDungeonMap>() {
                 map.revealed_tiles.iter_mut().for_each(|t| *t = true);
+                events.send(GameEvent::MapRevealed);
+            }
+
+            if let Ok(haste) = item.get_component::<ProvidesHaste>() {
+                let effect = SpeedEffect{ percent: 200, turns: haste.turns };
+                commands.add_component(activate.used_by, effect);
+                events.send(GameEvent::SpeedChanged {
+                    entity: EventEntity::of(ecs, activate.used_by),
+                    percent: effect.percent,
+                    turns: effect.turns,
+                });
+            }
+
+            if let Ok(slow) = item.get_component::<ProvidesSlow>() {
+                let effect = SpeedEffect{ percent: 50, turns: slow.turns };
+                let visible = area.clone().unwrap_or_else(|| ecs.entry_ref(activate.used_by)
+                    .ok()
+                    .and_then(|user| user.get_component::<FieldOfView>().ok().cloned())
+                    .map(|fov| fov.visible_tiles)
+                    .unwrap_or_default());
+                <(Entity, &Point)>::query()
+                    .filter(component::<Enemy>())
+                    .iter(ecs)
+                    .filter(|(_, pos)| visible.contains(pos))
+                    .for_each(|(enemy, _)| {
+                        commands.add_component(*enemy, effect);
+                        events.send(GameEvent::SpeedChanged {
+                            entity: EventEntity::of(ecs, *enemy),
+                            percent: effect.percent,
+                            turns: effect.turns,
+                        });
+                    });
             }
         }
 
@@ -29,7 +106,9 @@ pub fn use_items(
         commands.remove(*entity);
     });
 
-    for heal in healing_to_apply.iter() {
+    apply_damage(ecs, commands, events, wizard.invulnerable, &damage_to_apply);
+
+    for heal in &healing_to_apply {
         if let Ok(mut target) = ecs.entry_mut(heal.0) {
             if let Ok(health) = target.get_component_mut::<Health>() {
                 health.current = i32::min(
@@ -40,3 +119,65 @@ pub fn use_items(
         }
     }
 }
+
+// Damage is dealt the way combat deals it, with the user as the attacker.
+fn apply_damage(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    events: &mut Events,
+    invulnerable: bool,
+    damage_to_apply: &[(Entity, Entity, i32)]
+) {
+    for (attacker, victim, amount) in damage_to_apply {
+        let Ok(victim_entry) = ecs.entry_ref(*victim) else {
+            continue;
+        };
+        let is_player = victim_entry.get_component::<Player>().is_ok();
+        // Wizard mode can make the player shrug off blasts as well as blows.
+        if is_player && invulnerable {
+            continue;
+        }
+        let attacker_info = EventEntity::of(ecs, *attacker);
+        let victim_info = EventEntity::of(ecs, *victim);
+        if let Ok(health) = ecs.entry_mut(*victim).unwrap().get_component_mut::<Health>() {
+            let was_alive = health.current > 0;
+            health.current -= amount;
+            events.send(GameEvent::DamageDealt {
+                attacker: attacker_info.clone(),
+                victim: victim_info.clone(),
+                amount: *amount,
+            });
+            if was_alive && health.current < 1 {
+                events.send(GameEvent::EntityDied { victim: victim_info, killer: attacker_info });
+            }
+            if health.current < 1 && !is_player {
+                commands.remove(*victim);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blasts_spare_whoever_threw_them() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Map::new(10, 10));
+        resources.insert(Events::default());
+        resources.insert(Wizard::default());
+        let player = ecs.push((Player { map_level: 0 }, Point::new(2, 2), Health { current: 10, max: 10 }));
+        let goblin = ecs.push((Enemy, Point::new(3, 2), Health { current: 10, max: 10 }));
+        let fireball = ecs.push((Item, ProvidesDamage { amount: 4 }, AreaOfEffect { radius: 2 }));
+        ecs.push(((), ActivateItem { used_by: player, item: fireball, target: Some(Point::new(3, 2)) }));
+
+        let mut schedule = Schedule::builder().add_system(use_items_system()).build();
+        schedule.execute(&mut ecs, &mut resources);
+
+        let health = |entity| ecs.entry_ref(entity).unwrap().get_component::<Health>().unwrap().current;
+        assert_eq!(health(player), 10);
+        assert_eq!(health(goblin), 6);
+    }
+}
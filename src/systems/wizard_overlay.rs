@@ -0,0 +1,61 @@
+use crate::prelude::*;
+
+#[system]
+#[allow(clippy::trivially_copy_pass_by_ref)]
+#[read_component(Point)]
+#[read_component(FieldOfView)]
+#[read_component(Health)]
+#[read_component(Player)]
+pub fn wizard_overlay(
+    ecs: &SubWorld,
+    #[resource] wizard: &Wizard,
+    #[resource] camera: &Camera
+) {
+    if !wizard.enabled {
+        return;
+    }
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.print_color_centered(2, wizard.help(), ColorPair::new(MAGENTA, BLACK));
+
+    if wizard.show_entities {
+        let offset = camera.offset();
+
+        // What every entity can see, shaded on the map, whether or not the
+        // player can see it: the player's view in blue, the monsters' over
+        // it in purple.
+        draw_batch.target(0);
+        let mut views: Vec<(bool, &FieldOfView)> = <(&FieldOfView, Option<&Player>)>::query()
+            .iter(ecs)
+            .map(|(fov, player)| (player.is_some(), fov))
+            .collect();
+        views.sort_by_key(|(is_player, _)| !is_player);
+        for (is_player, fov) in views {
+            let shade = if is_player {
+                RGBA::from_u8(0, 0, 64, 255)
+            } else {
+                RGBA::from_u8(64, 0, 64, 255)
+            };
+            fov.visible_tiles
+                .iter()
+                .filter(|pt| camera.contains(**pt))
+                .for_each(|pt| {
+                    draw_batch.set_bg(*pt - offset, shade);
+                });
+        }
+
+        // Every entity's hit points, along the bottom of its tile.
+        draw_batch.target(2);
+        <(&Point, &Health)>::query()
+            .iter(ecs)
+            .filter(|(pt, _)| camera.contains(**pt))
+            .for_each(|(pt, health)| {
+                draw_batch.print_color(
+                    camera.text_pos(*pt) + Point::new(0, camera.text_cells() - 1),
+                    format!("{}/{}", health.current, health.max),
+                    ColorPair::new(MAGENTA, BLACK)
+                );
+            });
+    }
+    draw_batch.submit(10200).expect("Batch error");
+}
@@ -0,0 +1,80 @@
+use crate::prelude::*;
+use std::collections::HashSet;
+
+// An item waiting for the player to choose where to use it, and the cursor
+// they are choosing with. The resource is None whenever nothing is being
+// aimed.
+#[derive(Clone, Copy, Debug)]
+pub struct Targeting {
+    pub item: Entity,
+    pub range: i32,
+    pub radius: i32,
+    pub cursor: Point,
+}
+
+impl Targeting {
+    // Move the cursor a step, keeping it in view like look mode's.
+    pub fn move_by(&mut self, delta: Point, camera: &Camera, map: &Map) {
+        self.cursor = camera.clamp(self.cursor + delta, map);
+    }
+
+    // A tile can be aimed at if it's in range, the user can see it, nothing
+    // solid is in the way and it isn't asking the effect to land inside a
+    // wall.
+    pub fn is_valid(&self, pt: Point, origin: Point, visible: &HashSet<Point>, map: &Map) -> bool {
+        in_range(origin, pt, self.range) && visible.contains(&pt) && map.can_enter_tile(pt)
+            && line_of_fire(origin, pt, map)
+    }
+
+    // The tiles the effect would reach if it landed on the cursor.
+    pub fn area(&self, map: &Map) -> HashSet<Point> {
+        blast_area(self.cursor, self.radius, map)
+    }
+}
+
+pub fn in_range(origin: Point, pt: Point, range: i32) -> bool {
+    let delta = pt - origin;
+    delta.x * delta.x + delta.y * delta.y <= range * range
+}
+
+// Whether the straight line the overlay draws from the origin to the target
+// crosses only open tiles on the way.
+pub fn line_of_fire(origin: Point, target: Point, map: &Map) -> bool {
+    line2d_bresenham(origin, target)
+        .into_iter()
+        .filter(|pt| *pt != origin && *pt != target)
+        .all(|pt| map.try_idx(pt).is_some_and(|idx| !map.is_opaque(idx)))
+}
+
+// Everything within the radius of the target that can be seen from it, so
+// walls shelter whatever is behind them. A radius of 0 is the target alone.
+pub fn blast_area(target: Point, radius: i32, map: &Map) -> HashSet<Point> {
+    let mut area = if radius > 0 {
+        field_of_view_set(target, radius, map)
+    } else {
+        HashSet::new()
+    };
+    area.insert(target);
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_block_the_line_of_fire() {
+        // A pillar between the user and the target, which the user is
+        // allowed to see anyway.
+        let mut map = Map::new(10, 10);
+        let pillar = map.point2d_to_index(Point::new(3, 1));
+        map.tiles[pillar] = TileType::Wall;
+        let aim = Targeting { item: World::default().push(()), range: 6, radius: 0, cursor: Point::zero() };
+        let origin = Point::new(1, 1);
+        let visible: HashSet<Point> = [Point::new(5, 1), Point::new(5, 3), Point::new(3, 1)].into();
+
+        assert!(!aim.is_valid(Point::new(5, 1), origin, &visible, &map));
+        assert!(aim.is_valid(Point::new(5, 3), origin, &visible, &map));
+        assert!(!aim.is_valid(Point::new(3, 1), origin, &visible, &map));
+    }
+}
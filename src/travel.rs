@@ -0,0 +1,207 @@
+use crate::prelude::*;
+use std::collections::{HashSet, VecDeque};
+
+// The key that moves the player one step in the given direction, which is
+// also how they attack whatever stands there: a cursor key, or the numpad
+// for a diagonal.
+pub fn direction_key(delta: Point) -> Option<VirtualKeyCode> {
+    match (delta.x, delta.y) {
+        (-1, 0) => Some(VirtualKeyCode::Left),
+        (1, 0) => Some(VirtualKeyCode::Right),
+        (0, -1) => Some(VirtualKeyCode::Up),
+        (0, 1) => Some(VirtualKeyCode::Down),
+        (-1, -1) => Some(VirtualKeyCode::Numpad7),
+        (1, -1) => Some(VirtualKeyCode::Numpad9),
+        (-1, 1) => Some(VirtualKeyCode::Numpad1),
+        (1, 1) => Some(VirtualKeyCode::Numpad3),
+        _ => None,
+    }
+}
+
+// The step a movement key stands for: the cursor keys, the numpad and the
+// vi keys, with yubn for the diagonals.
+pub fn key_direction(key: VirtualKeyCode) -> Option<Point> {
+    match key {
+        VirtualKeyCode::Left | VirtualKeyCode::H | VirtualKeyCode::Numpad4 => Some(Point::new(-1, 0)),
+        VirtualKeyCode::Right | VirtualKeyCode::L | VirtualKeyCode::Numpad6 => Some(Point::new(1, 0)),
+        VirtualKeyCode::Up | VirtualKeyCode::K | VirtualKeyCode::Numpad8 => Some(Point::new(0, -1)),
+        VirtualKeyCode::Down | VirtualKeyCode::J | VirtualKeyCode::Numpad2 => Some(Point::new(0, 1)),
+        VirtualKeyCode::Y | VirtualKeyCode::Numpad7 | VirtualKeyCode::Home => Some(Point::new(-1, -1)),
+        VirtualKeyCode::U | VirtualKeyCode::Numpad9 | VirtualKeyCode::PageUp => Some(Point::new(1, -1)),
+        VirtualKeyCode::B | VirtualKeyCode::Numpad1 | VirtualKeyCode::End => Some(Point::new(-1, 1)),
+        VirtualKeyCode::N | VirtualKeyCode::Numpad3 | VirtualKeyCode::PageDown => Some(Point::new(1, 1)),
+        _ => None,
+    }
+}
+
+// A walk to a clicked tile, exploring the level or resting, taken one turn
+// at a time. Each turn is played as the key it stands for, so a replay
+// records travel like any other move.
+#[derive(Clone, Debug, Default)]
+pub struct Travel {
+    // The tile clicked this frame, waiting to be planned.
+    pub requested: Option<Point>,
+    path: VecDeque<Point>,
+    // Exploring picks each step afresh, as the level is revealed.
+    exploring: bool,
+    // Resting waits until the player is healed or disturbed.
+    resting: bool,
+    moved: bool,
+    // The enemies in view when the walk began, and the player's health on
+    // the last step; a new enemy or a loss of health stops the walk.
+    enemies_seen: HashSet<Entity>,
+    health: i32,
+}
+
+impl Travel {
+    pub fn start(&mut self, path: VecDeque<Point>, enemies_seen: HashSet<Entity>, health: i32) {
+        self.path = path;
+        self.exploring = false;
+        self.resting = false;
+        self.moved = false;
+        self.enemies_seen = enemies_seen;
+        self.health = health;
+    }
+
+    // Exploring stops for any enemy in view, so none count as seen.
+    pub fn explore(&mut self, health: i32) {
+        self.path.clear();
+        self.exploring = true;
+        self.resting = false;
+        self.moved = false;
+        self.enemies_seen.clear();
+        self.health = health;
+    }
+
+    pub fn rest(&mut self, health: i32) {
+        self.path.clear();
+        self.exploring = false;
+        self.resting = true;
+        self.moved = false;
+        self.enemies_seen.clear();
+        self.health = health;
+    }
+
+    pub fn stop(&mut self) {
+        self.path.clear();
+        self.exploring = false;
+        self.resting = false;
+        self.enemies_seen.clear();
+    }
+
+    pub fn is_exploring(&self) -> bool {
+        self.exploring
+    }
+
+    pub fn is_resting(&self) -> bool {
+        self.resting
+    }
+
+    pub fn has_moved(&self) -> bool {
+        self.moved
+    }
+
+    pub fn next_step(&self) -> Option<Point> {
+        self.path.front().copied()
+    }
+
+    pub fn take_step(&mut self, health: i32) {
+        self.path.pop_front();
+        self.moved = true;
+        self.health = health;
+    }
+
+    pub fn was_hurt(&self, health: i32) -> bool {
+        health < self.health
+    }
+
+    pub fn is_new_enemy(&self, enemy: Entity) -> bool {
+        !self.enemies_seen.contains(&enemy)
+    }
+}
+
+// The map as the player knows it, for planning a walk: only tiles they have
+// seen or can see, and no stairs along the way, since those would take them
+// off the level.
+struct KnownMap<'a> {
+    map: &'a Map,
+    visible: &'a HashSet<Point>,
+    destination: usize,
+}
+
+impl KnownMap<'_> {
+    fn is_known(&self, idx: usize) -> bool {
+        self.map.revealed_tiles[idx] || self.visible.contains(&self.map.index_to_point2d(idx))
+    }
+}
+
+impl BaseMap for KnownMap<'_> {
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        self.map.get_available_exits(idx)
+            .into_iter()
+            .filter(|(exit, _)| self.is_known(*exit))
+            .filter(|(exit, _)| self.map.tiles[*exit] == TileType::Floor
+                || *exit == self.destination)
+            .collect()
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2)
+    }
+
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.map.is_opaque(idx)
+    }
+}
+
+// The steps from start to a known destination, through tiles the player
+// knows, if there is a way.
+pub fn plan_path(
+    map: &Map,
+    visible: &HashSet<Point>,
+    start: Point,
+    destination: Point
+) -> Option<VecDeque<Point>> {
+    let destination_idx = map.try_idx(destination)?;
+    let known = KnownMap { map, visible, destination: destination_idx };
+    if !known.is_known(destination_idx) || start == destination {
+        return None;
+    }
+    let path = a_star_search(map.point2d_to_index(start), destination_idx, &known);
+    if !path.success {
+        return None;
+    }
+    Some(path.steps
+        .iter()
+        .skip(1)
+        .map(|idx| map.index_to_point2d(*idx))
+        .collect())
+}
+
+// The first step towards the nearest tile the player knows that borders
+// the unknown, along a Dijkstra map seeded from all of them. None once
+// there's nothing left within reach.
+pub fn explore_step(map: &Map, visible: &HashSet<Point>, start: Point) -> Option<Point> {
+    let known = KnownMap { map, visible, destination: usize::MAX };
+    let frontier: Vec<usize> = (0..map.tiles.len())
+        .filter(|idx| known.is_known(*idx) && map.tiles[*idx] == TileType::Floor)
+        .filter(|idx| {
+            let pt = map.index_to_point2d(*idx);
+            STEPS
+                .iter()
+                .filter_map(|delta| map.try_idx(pt + *delta))
+                .any(|neighbour| !known.is_known(neighbour))
+        })
+        .collect();
+    if frontier.is_empty() {
+        return None;
+    }
+
+    let dijkstra_map = DijkstraMap::new(map.width, map.height, &frontier, &known, 1024.0);
+    let start_idx = map.point2d_to_index(start);
+    if dijkstra_map.map[start_idx] >= f32::MAX {
+        return None;
+    }
+    DijkstraMap::find_lowest_exit(&dijkstra_map, start_idx, &known)
+        .map(|idx| map.index_to_point2d(idx))
+}
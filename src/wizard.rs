@@ -0,0 +1,105 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Cheats for testing the game. Wizard mode is always compiled in, but it
+// starts switched off unless the game is launched with --wizard.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Wizard {
+    pub enabled: bool,
+    pub invulnerable: bool,
+    pub show_entities: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WizardCommand {
+    Toggle,
+    RevealMap,
+    ToggleInvulnerable,
+    Spawn,
+    Teleport,
+    NextLevel,
+    ShowEntities,
+}
+
+impl WizardCommand {
+    // Every command but the one switching wizard mode on counts as cheating.
+    pub fn is_cheat(self) -> bool {
+        self != WizardCommand::Toggle
+    }
+}
+
+impl Wizard {
+    // The command the key stands for. Backquote switches wizard mode on and
+    // off, the others only work while it is on.
+    pub fn command(self, key: Option<VirtualKeyCode>) -> Option<WizardCommand> {
+        let command = match key? {
+            VirtualKeyCode::Grave => return Some(WizardCommand::Toggle),
+            VirtualKeyCode::M => WizardCommand::RevealMap,
+            VirtualKeyCode::I => WizardCommand::ToggleInvulnerable,
+            VirtualKeyCode::S => WizardCommand::Spawn,
+            VirtualKeyCode::T => WizardCommand::Teleport,
+            VirtualKeyCode::D => WizardCommand::NextLevel,
+            VirtualKeyCode::V => WizardCommand::ShowEntities,
+            _ => return None,
+        };
+        self.enabled.then_some(command)
+    }
+
+    pub fn help(self) -> String {
+        format!("Wizard: M map  I invulnerable ({})  S spawn  T teleport  D descend  V view ({})",
+            if self.invulnerable { "on" } else { "off" },
+            if self.show_entities { "on" } else { "off" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+
+    fn new_game(settings: GameSettings) -> State {
+        let mut state = State::new(Seed(3), settings);
+        state.keep_records = false;
+        state
+    }
+
+    fn turn_state(state: &State) -> TurnState {
+        *state.resources.get::<TurnState>().unwrap()
+    }
+
+    #[test]
+    fn nothing_is_conjured_into_walls_or_creatures() {
+        let mut state = new_game(GameSettings::default());
+        let tiles: Vec<(Point, TileType)> = {
+            let map = state.resources.get::<Map>().unwrap();
+            map.tiles.iter().enumerate().map(|(idx, tile)| (map.index_to_point2d(idx), *tile)).collect()
+        };
+        let wall = tiles.iter().find(|(_, tile)| *tile == TileType::Wall).unwrap().0;
+        let floor = tiles.iter()
+            .find(|(pos, tile)| *tile == TileType::Floor && state.wizard_spot(*pos).is_ok())
+            .unwrap().0;
+        let creature = *<&Point>::query()
+            .filter(component::<Health>())
+            .iter(&state.ecs)
+            .next()
+            .unwrap();
+
+        for pos in [wall, creature] {
+            state.wizard_command(WizardCommand::Spawn, pos);
+            assert_eq!(turn_state(&state), TurnState::AwaitingInput);
+        }
+        state.wizard_command(WizardCommand::Spawn, floor);
+        assert_eq!(turn_state(&state), TurnState::WizardSpawn);
+    }
+
+    #[test]
+    fn there_is_no_descending_from_the_last_level() {
+        let mut state = new_game(GameSettings::default());
+        state.wizard_command(WizardCommand::NextLevel, Point::zero());
+        assert_eq!(turn_state(&state), TurnState::NextLevel);
+
+        let mut state = new_game(GameSettings { start_level: 2, ..GameSettings::default() });
+        state.wizard_command(WizardCommand::NextLevel, Point::zero());
+        assert_eq!(turn_state(&state), TurnState::AwaitingInput);
+    }
+}